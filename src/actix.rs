@@ -0,0 +1,115 @@
+use crate::{Inner, TrackedFuture, WaitGroup, Waiter};
+use actix_service::{Service, Transform};
+use std::future::{self, Ready};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// An actix-web middleware that tracks every in-flight request with a worker of a
+/// [`WaitGroup`].
+///
+/// Register it with `App::wrap`. After `ServerHandle::stop`, waiting on the group resolves
+/// once every request in flight has its response.
+///
+/// ```rust
+/// use actix_service::{fn_service, Service, Transform};
+/// use waitgroup::{WaitGroup, WaitGroupMiddleware};
+/// # async_std::task::block_on(async {
+/// let wg = WaitGroup::new();
+/// // with actix-web: `App::new().wrap(WaitGroupMiddleware::new(&wg))`
+/// let service = WaitGroupMiddleware::new(&wg)
+///     .new_transform(fn_service(|req: u32| async move { Ok::<_, ()>(req) }))
+///     .await
+///     .unwrap();
+/// assert_eq!(service.call(1).await, Ok(1));
+///
+/// wg.wait().await;
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct WaitGroupMiddleware {
+    group: Arc<Inner>,
+}
+
+/// The service created by [`WaitGroupMiddleware`].
+pub struct WaitGroupMiddlewareService<S> {
+    service: S,
+    group: Arc<Inner>,
+}
+
+impl WaitGroupMiddleware {
+    pub fn new(wg: &WaitGroup) -> Self {
+        Self {
+            group: wg.inner.clone(),
+        }
+    }
+
+    /// Gets a handle to wait for the requests in flight.
+    pub fn waiter(&self) -> Waiter {
+        Waiter(self.group.clone())
+    }
+}
+
+impl<S, Req> Transform<S, Req> for WaitGroupMiddleware
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Transform = WaitGroupMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ready(Ok(WaitGroupMiddlewareService {
+            service,
+            group: self.group.clone(),
+        }))
+    }
+}
+
+impl<S> WaitGroupMiddlewareService<S> {
+    /// Gets the number of requests in flight.
+    pub fn in_flight(&self) -> usize {
+        self.group.workers()
+    }
+}
+
+impl<S, Req> Service<Req> for WaitGroupMiddlewareService<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = TrackedFuture<S::Future>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: Req) -> Self::Future {
+        let worker = self.group.acquire();
+        TrackedFuture::new(self.service.call(req), worker)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_service::fn_service;
+
+    #[async_std::test]
+    async fn in_flight() {
+        let wg = WaitGroup::new();
+        let middleware = WaitGroupMiddleware::new(&wg);
+        let service = middleware
+            .new_transform(fn_service(|req: u32| async move { Ok::<_, ()>(req * 2) }))
+            .await
+            .unwrap();
+        let a = service.call(1);
+        let b = service.call(2);
+        assert_eq!(service.in_flight(), 2);
+        assert_eq!(a.await, Ok(2));
+        drop(b);
+        middleware.waiter().wait().await;
+    }
+}
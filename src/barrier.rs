@@ -0,0 +1,150 @@
+use crate::waiters::Waiters;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+/// A barrier that releases a fixed number of tasks together.
+///
+/// The barrier is cyclic: once all participants have arrived it resets for the next round.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use waitgroup::Barrier;
+/// use async_std::task;
+/// # task::block_on(async {
+/// let barrier = Arc::new(Barrier::new(10));
+/// let tasks: Vec<_> = (0..10)
+///     .map(|_| {
+///         let barrier = barrier.clone();
+///         task::spawn(async move { barrier.arrive().await.is_leader() })
+///     })
+///     .collect();
+///
+/// let mut leaders = 0;
+/// for task in tasks {
+///     leaders += task.await as usize;
+/// }
+/// assert_eq!(leaders, 1);
+/// # });
+/// ```
+pub struct Barrier {
+    n: usize,
+    state: Mutex<State>,
+    waiters: Waiters,
+}
+
+struct State {
+    arrived: usize,
+    generation: usize,
+}
+
+/// Future returned by [`Barrier::arrive`].
+pub struct Arrive<'a> {
+    barrier: &'a Barrier,
+    generation: usize,
+    is_leader: bool,
+    key: Option<usize>,
+}
+
+/// Returned by [`Arrive`] once all participants have arrived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// Returns `true` for exactly one participant of each round.
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl Barrier {
+    /// Creates a barrier for `n` participants.
+    ///
+    /// A barrier for zero participants behaves like one for a single participant.
+    pub fn new(n: usize) -> Self {
+        Self {
+            n: n.max(1),
+            state: Mutex::new(State {
+                arrived: 0,
+                generation: 0,
+            }),
+            waiters: Waiters::new(),
+        }
+    }
+
+    /// Arrives at the barrier and waits for the other participants.
+    ///
+    /// The arrival counts as soon as this is called, even if the future is dropped.
+    pub fn arrive(&self) -> Arrive<'_> {
+        let mut state = self.state.lock().unwrap();
+        let generation = state.generation;
+        state.arrived += 1;
+        let is_leader = state.arrived == self.n;
+        if is_leader {
+            state.arrived = 0;
+            state.generation += 1;
+            drop(state);
+            self.waiters.wake_all();
+        }
+        Arrive {
+            barrier: self,
+            generation,
+            is_leader,
+            key: None,
+        }
+    }
+
+    fn is_released(&self, generation: usize) -> bool {
+        self.state.lock().unwrap().generation != generation
+    }
+}
+
+impl Future for Arrive<'_> {
+    type Output = BarrierWaitResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if !this.barrier.is_released(this.generation) {
+            this.barrier.waiters.register(&mut this.key, cx.waker(), ());
+            // the last participant may have arrived before the waker was registered
+            if !this.barrier.is_released(this.generation) {
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(BarrierWaitResult(this.is_leader))
+    }
+}
+
+impl Drop for Arrive<'_> {
+    fn drop(&mut self) {
+        self.barrier.waiters.remove(&mut self.key);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::task;
+    use std::sync::Arc;
+
+    #[async_std::test]
+    async fn cyclic() {
+        let barrier = Arc::new(Barrier::new(10));
+
+        for _ in 0..3 {
+            let tasks: Vec<_> = (0..10)
+                .map(|_| {
+                    let barrier = barrier.clone();
+                    task::spawn(async move { barrier.arrive().await })
+                })
+                .collect();
+
+            let mut leaders = 0;
+            for task in tasks {
+                leaders += task.await.is_leader() as usize;
+            }
+            assert_eq!(leaders, 1);
+        }
+    }
+}
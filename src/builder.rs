@@ -0,0 +1,267 @@
+use crate::drain::Stages;
+use crate::observer::Observer;
+use crate::registry::{self, Registry};
+use crate::waiters::Waiters;
+use crate::{DrainReport, Epoch, ErrGroup, Escalation, Inner, WaitGroup, WaitGroupObserver};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Configures and creates a [`WaitGroup`].
+///
+/// ```rust
+/// use waitgroup::WaitGroup;
+///
+/// let wg = WaitGroup::builder().strict(true).build();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    name: Option<Arc<str>>,
+    strict: bool,
+    max_workers: Option<usize>,
+    release_overdue: bool,
+    collect_errors: bool,
+    cancel_on_panic: bool,
+    stages: Stages,
+    #[cfg(feature = "tokio-util")]
+    parent_token: Option<tokio_util::sync::CancellationToken>,
+    #[cfg(feature = "async-channel")]
+    completions: Option<async_channel::Sender<crate::Completion>>,
+    observer: Option<Observer>,
+    #[cfg(feature = "otel")]
+    tracer: Option<Arc<opentelemetry::global::BoxedTracer>>,
+    #[cfg(feature = "otel")]
+    meter: Option<opentelemetry::metrics::Meter>,
+}
+
+/// Configures and creates an [`ErrGroup`] with errors of type `E`.
+///
+/// Created by [`Builder::error_type`].
+pub struct ErrGroupBuilder<E> {
+    builder: Builder,
+    _error: PhantomData<fn() -> E>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names the group and lists it in [`WaitGroup::named_groups`] for as long as it is
+    /// alive.
+    pub fn name(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Makes waiting on a group that never had any workers a bug.
+    ///
+    /// When enabled, [`WaitGroup::wait_result`] and [`WaitGroup::wait_errors`] report a
+    /// [`NoWorkers`](crate::NoWorkers) error if no worker was ever created, and with the `log`
+    /// or `tracing` feature every other wait logs a warning. This catches code paths that
+    /// silently skip spawning their work.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Limits the number of workers that can be active at the same time.
+    ///
+    /// Workers created with [`WaitGroup::acquire`] wait for a free slot, which turns the
+    /// group into a semaphore that can also be waited on. A limit of zero is treated as one.
+    pub fn max_workers(mut self, n: usize) -> Self {
+        self.max_workers = Some(n.max(1));
+        self
+    }
+
+    /// Stops waiting for workers whose deadline has passed.
+    ///
+    /// While the group is waited on, the units of a worker created with
+    /// [`WaitGroup::worker_with_deadline`] are released once its deadline passes, so one stuck
+    /// task cannot hang a shutdown forever. The overrun is reported either way.
+    pub fn release_overdue(mut self, release: bool) -> Self {
+        self.release_overdue = release;
+        self
+    }
+
+    /// Keeps every error reported by [`Worker::fail`](crate::Worker::fail), not just the first.
+    ///
+    /// Use [`WaitGroup::wait_errors`] to get all of them.
+    pub fn collect_errors(mut self, collect: bool) -> Self {
+        self.collect_errors = collect;
+        self
+    }
+
+    /// Sends a [`Completion`](crate::Completion) to `sender` every time a worker finishes.
+    ///
+    /// Messages are sent without waiting, so they are dropped while the channel is full.
+    #[cfg(feature = "async-channel")]
+    pub fn completions(mut self, sender: async_channel::Sender<crate::Completion>) -> Self {
+        self.completions = Some(sender);
+        self
+    }
+
+    /// Reports the lifecycle of the group's workers to `observer`.
+    pub fn observer(mut self, observer: impl WaitGroupObserver) -> Self {
+        self.observer = Some(Observer(Arc::new(observer)));
+        self
+    }
+
+    /// Starts the group's OpenTelemetry spans with `tracer` instead of the global tracer.
+    #[cfg(feature = "otel")]
+    pub fn tracer(mut self, tracer: opentelemetry::global::BoxedTracer) -> Self {
+        self.tracer = Some(Arc::new(tracer));
+        self
+    }
+
+    /// Records the group's OpenTelemetry metrics with `meter` instead of the global meter.
+    #[cfg(feature = "otel")]
+    pub fn meter(mut self, meter: opentelemetry::metrics::Meter) -> Self {
+        self.meter = Some(meter);
+        self
+    }
+
+    /// Raises the group's cancellation signal when the first worker panics.
+    ///
+    /// The sibling workers can then stop early, see [`WaitGroup::panicked`] for how panics
+    /// are detected.
+    pub fn cancel_on_panic(mut self, cancel: bool) -> Self {
+        self.cancel_on_panic = cancel;
+        self
+    }
+
+    /// Makes the group's cancellation signal a child of `token`.
+    ///
+    /// Cancelling `token` cancels the group, but not the other way around, so the group
+    /// fits into an existing tokio shutdown tree. See [`WaitGroup::child_token`] for the
+    /// other direction.
+    #[cfg(feature = "tokio-util")]
+    pub fn cancellation_token(mut self, token: tokio_util::sync::CancellationToken) -> Self {
+        self.parent_token = Some(token);
+        self
+    }
+
+    /// Runs `stage` when a drain of the group is still waiting `after` it started.
+    ///
+    /// Stages apply to [`WaitGroup::drain`] and [`Shutdown`](crate::Shutdown), and run in the
+    /// order of their delay. This encodes a soft to hard shutdown policy:
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use waitgroup::{Escalation, WaitGroup};
+    ///
+    /// let wg = WaitGroup::builder()
+    ///     .escalate(Duration::from_secs(5), |report| {
+    ///         eprintln!("still waiting for {:?}", report.pending());
+    ///         Escalation::Continue
+    ///     })
+    ///     .escalate(Duration::from_secs(15), |_| Escalation::Detach)
+    ///     .build();
+    /// ```
+    pub fn escalate(
+        mut self,
+        after: Duration,
+        stage: impl Fn(&DrainReport) -> Escalation + Send + Sync + 'static,
+    ) -> Self {
+        self.stages.0.push((after, Arc::new(stage)));
+        self.stages.0.sort_by_key(|(after, _)| *after);
+        self
+    }
+
+    /// Creates an [`ErrGroup`] carrying errors of type `E` instead of a group.
+    ///
+    /// `E` is the error of [`ErrGroup::track`], [`ErrGroup::fail`] and [`ErrGroup::wait`].
+    /// The underlying group's errors, from [`Worker::fail`](crate::Worker::fail) and read by
+    /// [`WaitGroup::wait_result`] and [`WaitGroup::wait_errors`], stay boxed.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    ///
+    /// #[derive(Debug)]
+    /// struct MyError;
+    ///
+    /// let group = WaitGroup::builder().strict(true).error_type::<MyError>().build();
+    /// group.fail(MyError);
+    /// ```
+    pub fn error_type<E>(self) -> ErrGroupBuilder<E> {
+        ErrGroupBuilder {
+            builder: self,
+            _error: PhantomData,
+        }
+    }
+
+    pub fn build(self) -> WaitGroup {
+        let named = self.name.is_some();
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("waitgroup", name = self.name.as_deref());
+        #[cfg(feature = "otel")]
+        let telemetry = crate::otel::Telemetry::new(
+            self.name.as_deref(),
+            self.tracer.clone(),
+            self.meter.clone(),
+        );
+        let wg = WaitGroup {
+            inner: Arc::new_cyclic(|group| Inner {
+                name: self.name,
+                count: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+                total: AtomicUsize::new(0),
+                completed: AtomicUsize::new(0),
+                overruns: AtomicUsize::new(0),
+                panicked: AtomicUsize::new(0),
+                succeeded: AtomicUsize::new(0),
+                failed: AtomicUsize::new(0),
+                errors: Mutex::new(Vec::new()),
+                values: Mutex::default(),
+                loose: Mutex::default(),
+                collect_errors: self.collect_errors,
+                cancel_on_panic: self.cancel_on_panic,
+                #[cfg(feature = "async-channel")]
+                completions: self.completions,
+                #[cfg(feature = "tracing")]
+                span,
+                #[cfg(feature = "otel")]
+                telemetry,
+                observer: self.observer,
+                stages: self.stages,
+                children: Mutex::new(Vec::new()),
+                parent: Mutex::new(None),
+                #[cfg(feature = "tokio-util")]
+                token: match self.parent_token {
+                    Some(parent) => parent.child_token(),
+                    None => tokio_util::sync::CancellationToken::new(),
+                },
+                generation: AtomicUsize::new(0),
+                created: Instant::now(),
+                threshold: AtomicUsize::new(1),
+                watch_completed: AtomicBool::new(false),
+                cancelled: AtomicBool::new(false),
+                epoch: Mutex::new(Arc::new(Epoch {
+                    group: group.clone(),
+                    next: Mutex::new(None),
+                    round: Arc::default(),
+                })),
+                on_drain: Mutex::new(Vec::new()),
+                next_id: AtomicU64::new(0),
+                registry: Mutex::new(Registry::default()),
+                waiters: Waiters::new(),
+                strict: self.strict,
+                max_workers: self.max_workers.unwrap_or(usize::MAX),
+                release_overdue: self.release_overdue,
+            }),
+            key: None,
+            polled: None,
+        };
+        if named {
+            registry::register_group(&wg.inner);
+        }
+        wg
+    }
+}
+
+impl<E> ErrGroupBuilder<E> {
+    pub fn build(self) -> ErrGroup<E> {
+        ErrGroup::with_group(self.builder.build())
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{Inner, WaitGroup};
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::MetricFamily;
+use prometheus::{IntCounter, IntGauge, Opts};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, Weak};
+
+/// A prometheus collector that exposes the statistics of a [`WaitGroup`].
+///
+/// The metrics are named `{namespace}_active_workers`, `{namespace}_peak_workers`,
+/// `{namespace}_workers_total` and `{namespace}_failed_workers_total`, and carry a `group`
+/// label if the group is named. They are read from the group on every scrape, and no longer
+/// reported once the group is dropped.
+///
+/// ```rust
+/// use waitgroup::{WaitGroup, WaitGroupCollector};
+/// let wg = WaitGroup::builder().name("jobs").build();
+/// let registry = prometheus::Registry::new();
+/// registry
+///     .register(Box::new(WaitGroupCollector::new(&wg, "jobs").unwrap()))
+///     .unwrap();
+/// let _worker = wg.worker();
+/// assert_eq!(registry.gather().len(), 4);
+/// ```
+pub struct WaitGroupCollector {
+    group: Weak<Inner>,
+    active: IntGauge,
+    peak: IntGauge,
+    total: IntCounter,
+    failed: IntCounter,
+    // counters can only be incremented, this keeps two scrapes from adding the same workers
+    scrape: Mutex<()>,
+}
+
+impl WaitGroupCollector {
+    /// Creates the collector for `wg`, with metric names starting with `namespace`.
+    pub fn new(wg: &WaitGroup, namespace: &str) -> prometheus::Result<Self> {
+        let opts = |name: &str, help: &str| {
+            let opts = Opts::new(name, help).namespace(namespace);
+            match &wg.inner.name {
+                Some(group) => opts.const_label("group", &**group),
+                None => opts,
+            }
+        };
+        Ok(Self {
+            group: Arc::downgrade(&wg.inner),
+            active: IntGauge::with_opts(opts("active_workers", "Number of active workers."))?,
+            peak: IntGauge::with_opts(opts(
+                "peak_workers",
+                "Maximum number of workers active at the same time.",
+            ))?,
+            total: IntCounter::with_opts(opts("workers_total", "Number of workers created."))?,
+            failed: IntCounter::with_opts(opts(
+                "failed_workers_total",
+                "Number of workers that finished with an error.",
+            ))?,
+            scrape: Mutex::new(()),
+        })
+    }
+}
+
+impl Collector for WaitGroupCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        let mut descs = self.active.desc();
+        descs.extend(self.peak.desc());
+        descs.extend(self.total.desc());
+        descs.extend(self.failed.desc());
+        descs
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let group = match self.group.upgrade() {
+            Some(group) => group,
+            None => return Vec::new(),
+        };
+        self.active.set(group.workers() as i64);
+        self.peak.set(group.peak.load(Ordering::Relaxed) as i64);
+        let _scrape = self.scrape.lock().unwrap();
+        let advance = |counter: &IntCounter, value: usize| {
+            counter.inc_by((value as u64).saturating_sub(counter.get()))
+        };
+        advance(&self.total, group.total.load(Ordering::Relaxed));
+        advance(&self.failed, group.failed.load(Ordering::Relaxed));
+        let mut families = self.active.collect();
+        families.extend(self.peak.collect());
+        families.extend(self.total.collect());
+        families.extend(self.failed.collect());
+        families
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn collect() {
+        let wg = WaitGroup::builder().name("jobs").build();
+        let collector = WaitGroupCollector::new(&wg, "test").unwrap();
+        let workers = wg.workers_batch(3);
+        drop(workers);
+        let worker = wg.worker();
+        wg.worker().complete_err();
+
+        let families = collector.collect();
+        let values: Vec<_> = families
+            .iter()
+            .map(|family| {
+                let metric = &family.get_metric()[0];
+                assert_eq!(metric.get_label()[0].get_value(), "jobs");
+                let value = match family.get_name() {
+                    "test_active_workers" | "test_peak_workers" => metric.get_gauge().get_value(),
+                    _ => metric.get_counter().get_value(),
+                };
+                (family.get_name(), value)
+            })
+            .collect();
+        assert_eq!(
+            values,
+            [
+                ("test_active_workers", 1.0),
+                ("test_peak_workers", 3.0),
+                ("test_workers_total", 5.0),
+                ("test_failed_workers_total", 1.0),
+            ]
+        );
+        // counters keep their value on the next scrape
+        assert_eq!(
+            collector.collect()[2].get_metric()[0]
+                .get_counter()
+                .get_value(),
+            5.0
+        );
+
+        drop((wg, worker));
+        assert!(collector.collect().is_empty());
+    }
+}
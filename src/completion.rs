@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+/// A message sent for every finished worker of a group configured with
+/// [`Builder::completions`](crate::Builder::completions).
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub(crate) id: u64,
+    pub(crate) name: Option<Arc<str>>,
+    pub(crate) tag: Option<&'static str>,
+    pub(crate) completed: bool,
+    pub(crate) remaining: usize,
+}
+
+impl Completion {
+    /// Gets the id of the worker, see [`Worker::id`](crate::Worker::id).
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Gets the label of the worker, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Gets the tag of the worker, if it has one.
+    pub fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
+
+    /// Returns `false` if the worker was [`detach`](crate::Worker::detach)ed instead.
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    /// Gets the number of workers that were still active when this one finished.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::WaitGroup;
+
+    #[async_std::test]
+    async fn completions() {
+        let (tx, rx) = async_channel::bounded(1);
+        let wg = WaitGroup::builder().completions(tx).build();
+        let a = wg.named_worker("a");
+        let b = wg.worker();
+        drop(a);
+        // the channel is full, so this completion is skipped
+        b.detach();
+
+        let completion = rx.recv().await.unwrap();
+        assert_eq!(completion.name(), Some("a"));
+        assert!(completion.is_completed());
+        assert_eq!(completion.remaining(), 1);
+        assert!(rx.try_recv().is_err());
+
+        let worker = wg.worker();
+        let id = worker.id();
+        worker.detach();
+        let completion = rx.recv().await.unwrap();
+        assert_eq!(completion.id(), id);
+        assert!(!completion.is_completed());
+    }
+}
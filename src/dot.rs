@@ -0,0 +1,96 @@
+use crate::Inner;
+use std::fmt::Write;
+use std::sync::{Arc, Weak};
+use std::time::Instant;
+
+/// Renders `root` and the groups linked below it as a DOT graph.
+pub(crate) fn dump(root: &Arc<Inner>) -> String {
+    let mut out = String::from("digraph waitgroup {\n");
+    let now = Instant::now();
+    // the groups already rendered, linked children may form a cycle
+    let mut seen = vec![Arc::as_ptr(root)];
+    let mut queue = vec![root.clone()];
+    let mut next = 0;
+    while let Some(inner) = queue.get(next).cloned() {
+        let node = format!("g{}", next);
+        next += 1;
+
+        let mut label = escape(inner.name.as_deref().unwrap_or("group"));
+        write!(label, "\\n{} workers", inner.workers()).unwrap();
+        if inner.is_cancelled() {
+            label.push_str(", cancelled");
+        }
+        writeln!(out, "    {} [label=\"{}\"];", node, label).unwrap();
+
+        let workers = inner.registry.lock().unwrap().snapshot();
+        for info in workers {
+            let name = match (info.name(), info.tag()) {
+                (Some(name), _) => escape(name),
+                (None, Some(tag)) => escape(tag),
+                (None, None) => format!("#{}", info.id()),
+            };
+            let state = if info.expired {
+                "expired"
+            } else if info.deadline().map_or(false, |deadline| deadline <= now) {
+                "overdue"
+            } else {
+                "active"
+            };
+            writeln!(
+                out,
+                "    {}_w{} [shape=box, label=\"{}\\n{}\"];",
+                node,
+                info.id(),
+                name,
+                state
+            )
+            .unwrap();
+            writeln!(out, "    {} -> {}_w{};", node, node, info.id()).unwrap();
+        }
+
+        let children = inner.children.lock().unwrap().clone();
+        for child in children.iter().filter_map(Weak::upgrade) {
+            let index = match seen.iter().position(|&ptr| ptr == Arc::as_ptr(&child)) {
+                Some(index) => index,
+                None => {
+                    seen.push(Arc::as_ptr(&child));
+                    queue.push(child);
+                    seen.len() - 1
+                }
+            };
+            writeln!(out, "    {} -> g{};", node, index).unwrap();
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::WaitGroup;
+
+    #[test]
+    fn dump_dot() {
+        let wg = WaitGroup::named("server");
+        let stage = wg.subgroup();
+        let _fetch = stage.named_worker("fetch \"a\"");
+        let _w = wg.worker();
+        stage.link_child(&wg);
+
+        assert_eq!(
+            wg.dump_dot(),
+            "digraph waitgroup {\n    \
+                g0 [label=\"server\\n2 workers\"];\n    \
+                g0 -> g1;\n    \
+                g1 [label=\"group\\n1 workers\"];\n    \
+                g1_w0 [shape=box, label=\"fetch \\\"a\\\"\\nactive\"];\n    \
+                g1 -> g1_w0;\n    \
+                g1 -> g0;\n\
+            }\n"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{Inner, WaitGroupFuture, WorkerInfo};
+use futures_timer::Delay;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Future returned by [`WaitGroup::drain`](crate::WaitGroup::drain).
+pub struct Drain {
+    inner: Arc<Inner>,
+    wait: WaitGroupFuture,
+    delay: Option<Delay>,
+    started: Instant,
+    // index of the next escalation stage and its timer
+    stage: usize,
+    stage_delay: Option<Delay>,
+}
+
+/// What to do after an escalation stage of a drain, see
+/// [`Builder::escalate`](crate::Builder::escalate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escalation {
+    /// Keep waiting for the remaining workers.
+    Continue,
+    /// Stop waiting and report the remaining workers as stragglers.
+    GiveUp,
+    /// Like [`GiveUp`](Self::GiveUp), but also remove the stragglers from the group's count
+    /// and [`pending`](crate::WaitGroup::pending) workers. Dropping them later has no effect.
+    Detach,
+}
+
+pub(crate) type Stage = (
+    Duration,
+    Arc<dyn Fn(&DrainReport) -> Escalation + Send + Sync>,
+);
+
+/// The escalation stages of a group, sorted by their delay.
+#[derive(Clone, Default)]
+pub(crate) struct Stages(pub(crate) Vec<Stage>);
+
+impl fmt::Debug for Stages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|(after, _)| after))
+            .finish()
+    }
+}
+
+/// The outcome of draining a group, listing the stragglers if it did not finish in time.
+#[derive(Debug, Clone)]
+pub struct DrainReport {
+    remaining: usize,
+    // the part of `remaining` held by workers missing from `pending`
+    unlisted: usize,
+    pending: Vec<WorkerInfo>,
+    elapsed: Duration,
+}
+
+impl Drain {
+    /// Waits for the current workers of `inner`, giving up after `timeout` if there is one.
+    pub(crate) fn new(inner: &Arc<Inner>, timeout: Option<Duration>) -> Self {
+        let mut drain = Self {
+            inner: inner.clone(),
+            wait: inner.wait(),
+            delay: timeout.map(Delay::new),
+            started: Instant::now(),
+            stage: 0,
+            stage_delay: None,
+        };
+        drain.stage_delay = drain.next_stage_delay();
+        drain
+    }
+
+    fn next_stage_delay(&self) -> Option<Delay> {
+        let (after, _) = self.inner.stages.0.get(self.stage)?;
+        let at = self.started + *after;
+        Some(Delay::new(at.saturating_duration_since(Instant::now())))
+    }
+
+    fn report(&self) -> DrainReport {
+        // the count is read under the registry's lock, listed workers are added to it before
+        // and removed after the registry, so every listed worker is part of the count
+        let registry = self.inner.registry.lock().unwrap();
+        let remaining = self.inner.workers();
+        let pending = if remaining > 0 {
+            registry.snapshot()
+        } else {
+            Vec::new()
+        };
+        drop(registry);
+        let listed: usize = pending
+            .iter()
+            .filter(|info| !info.expired)
+            .map(|info| info.weight)
+            .sum();
+        DrainReport {
+            remaining,
+            unlisted: remaining.saturating_sub(listed),
+            pending,
+            elapsed: self.started.elapsed(),
+        }
+    }
+
+    /// Runs the stages that are due, returning the final report if one of them gave up.
+    fn poll_stages(&mut self, cx: &mut Context<'_>) -> Option<DrainReport> {
+        while let Some(delay) = &mut self.stage_delay {
+            if Pin::new(delay).poll(cx).is_pending() {
+                return None;
+            }
+            let (_, stage) = self.inner.stages.0[self.stage].clone();
+            self.stage += 1;
+            self.stage_delay = self.next_stage_delay();
+            match stage(&self.report()) {
+                Escalation::Continue => {}
+                Escalation::GiveUp => return Some(self.report()),
+                Escalation::Detach => {
+                    let report = self.report();
+                    self.inner.detach_all();
+                    return Some(report);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl DrainReport {
+    /// Returns `true` if all workers finished in time.
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Gets the number of units that did not finish in time, like [`WaitGroup::workers`].
+    ///
+    /// This includes the workers listed by [`pending`](Self::pending), counted by their
+    /// weight, and the [`unlisted`](Self::unlisted) ones.
+    ///
+    /// [`WaitGroup::workers`]: crate::WaitGroup::workers
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Gets the number of units of [`remaining`](Self::remaining) held by plain workers,
+    /// which are not listed by [`pending`](Self::pending).
+    pub fn unlisted(&self) -> usize {
+        self.unlisted
+    }
+
+    /// Gets the named, tagged, and workers with metadata that did not finish in time.
+    pub fn pending(&self) -> &[WorkerInfo] {
+        &self.pending
+    }
+
+    /// Gets the time from starting the drain until the workers finished or the timeout.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+impl Future for Drain {
+    type Output = DrainReport;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if Pin::new(&mut this.wait).poll(cx).is_pending() {
+            if let Some(report) = this.poll_stages(cx) {
+                return Poll::Ready(report);
+            }
+            let elapsed = match &mut this.delay {
+                Some(delay) => Pin::new(delay).poll(cx).is_ready(),
+                None => false,
+            };
+            if !elapsed {
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(this.report())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::WaitGroup;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn drain() {
+        let wg = WaitGroup::new();
+        assert!(wg.drain(Duration::from_secs(1)).await.is_complete());
+
+        let _stuck = wg.worker_with("job-7");
+        let _plain = wg.worker();
+        let _heavy = wg.worker_weighted(3);
+        let report = wg.drain(Duration::from_millis(10)).await;
+        assert_eq!(report.remaining(), 5);
+        assert_eq!(report.unlisted(), 4);
+        assert_eq!(report.pending().len(), 1);
+        assert_eq!(report.pending()[0].meta::<&str>(), Some(&"job-7"));
+    }
+
+    #[async_std::test]
+    async fn escalate() {
+        use super::Escalation;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let logged = Arc::new(AtomicUsize::new(0));
+        let counter = logged.clone();
+        let wg = WaitGroup::builder()
+            .escalate(Duration::from_millis(20), |_| Escalation::GiveUp)
+            .escalate(Duration::from_millis(1), move |report| {
+                counter.store(report.remaining(), Ordering::SeqCst);
+                Escalation::Continue
+            })
+            .build();
+        let _stuck = wg.worker();
+        let report = wg.drain(Duration::from_secs(60)).await;
+        assert_eq!(logged.load(Ordering::SeqCst), 1);
+        assert!(!report.is_complete());
+    }
+
+    #[async_std::test]
+    async fn detach() {
+        use super::Escalation;
+
+        let wg = WaitGroup::builder()
+            .escalate(Duration::from_millis(1), |_| Escalation::Detach)
+            .build();
+        let stuck = wg.worker_with("job-7");
+        let split = wg.worker().split(2);
+        let report = wg.drain(Duration::from_secs(60)).await;
+        assert_eq!(report.remaining(), 3);
+        assert_eq!(report.pending()[0].meta::<&str>(), Some(&"job-7"));
+        assert_eq!(wg.workers(), 0);
+        assert!(wg.pending().is_empty());
+
+        // the stragglers no longer count, new workers do
+        let worker = wg.worker();
+        drop((stuck, split));
+        assert_eq!(wg.workers(), 1);
+        drop(worker);
+        wg.wait().await;
+    }
+
+    #[async_std::test]
+    async fn detach_added() {
+        use super::Escalation;
+
+        let wg = WaitGroup::builder()
+            .escalate(Duration::from_millis(1), |_| Escalation::Detach)
+            .build();
+        wg.add(2);
+        let report = wg.drain(Duration::from_secs(60)).await;
+        assert_eq!((report.remaining(), report.unlisted()), (2, 2));
+        assert_eq!(wg.workers(), 0);
+
+        // the oldest units were detached, finishing them has no effect
+        wg.add(1);
+        wg.done();
+        wg.done();
+        assert_eq!(wg.workers(), 1);
+        wg.done();
+        assert_eq!(wg.workers(), 0);
+    }
+}
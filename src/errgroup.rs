@@ -0,0 +1,232 @@
+use crate::{BoxError, Inner, Until, WaitGroup, WaitGroupFuture, Worker};
+use pin_project_lite::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// A group of tasks that fail together, modeled after Go's `errgroup`.
+///
+/// The first error reported by a task is kept and raises the cancellation signal, so the
+/// other tasks can stop early. [`wait`](Self::wait) still waits for every task.
+///
+/// The error type defaults to [`BoxError`], use [`Builder::error_type`](crate::Builder::error_type)
+/// to configure the group with a concrete one.
+///
+/// ```rust
+/// use waitgroup::ErrGroup;
+/// use async_std::task;
+/// # task::block_on(async {
+/// let group = ErrGroup::new();
+/// task::spawn(group.track(async { Err("connection refused") }));
+/// let cancelled = group.cancelled();
+/// task::spawn(group.track(async move {
+///     // stop on the first failure
+///     cancelled.await;
+///     Ok(())
+/// }));
+///
+/// assert_eq!(group.wait().await, Err("connection refused"));
+/// # });
+/// ```
+pub struct ErrGroup<E = BoxError> {
+    wg: WaitGroup,
+    error: Arc<Mutex<Option<E>>>,
+}
+
+pin_project! {
+    /// Future returned by [`ErrGroup::track`].
+    pub struct ErrTracked<F, E> {
+        #[pin]
+        future: F,
+        worker: Option<Worker>,
+        error: Arc<Mutex<Option<E>>>,
+    }
+}
+
+/// Future returned by [`ErrGroup::wait`].
+pub struct ErrWait<E> {
+    wait: WaitGroupFuture,
+    error: Arc<Mutex<Option<E>>>,
+}
+
+impl<E> ErrGroup<E> {
+    pub fn new() -> Self {
+        Self::with_group(WaitGroup::new())
+    }
+
+    pub(crate) fn with_group(wg: WaitGroup) -> Self {
+        Self {
+            wg,
+            error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Wraps `future` so that the group waits for it and an error fails the group.
+    pub fn track<F>(&self, future: F) -> ErrTracked<F, E>
+    where
+        F: Future<Output = Result<(), E>>,
+    {
+        ErrTracked {
+            future,
+            worker: Some(self.wg.worker()),
+            error: self.error.clone(),
+        }
+    }
+
+    /// Creates a worker that does not report a result, see [`fail`](Self::fail).
+    ///
+    /// Errors passed to [`Worker::fail`] are not seen by [`wait`](Self::wait), report them
+    /// with [`fail`](Self::fail) instead.
+    pub fn worker(&self) -> Worker {
+        self.wg.worker()
+    }
+
+    /// Fails the group with `error`, unless it already failed.
+    pub fn fail(&self, error: E) {
+        fail(&self.wg.inner, &self.error, error);
+    }
+
+    /// Returns `true` once a task has failed.
+    pub fn is_cancelled(&self) -> bool {
+        self.wg.inner.is_cancelled()
+    }
+
+    /// Waits for the first task to fail.
+    pub fn cancelled(&self) -> WaitGroupFuture {
+        self.wg.inner.wait_for(Until::Cancelled)
+    }
+
+    /// Gets the number of active tasks.
+    pub fn workers(&self) -> usize {
+        self.wg.workers()
+    }
+
+    /// Waits for all tasks to finish and resolves to the first error, if any.
+    ///
+    /// The error is taken out of the group, so it is only returned once.
+    pub fn wait(&self) -> ErrWait<E> {
+        ErrWait {
+            wait: self.wg.wait(),
+            error: self.error.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl ErrGroup<anyhow::Error> {
+    /// Like [`track`](Self::track), for futures failing with anything that converts into an
+    /// [`anyhow::Error`].
+    pub fn track_anyhow<F, E>(
+        &self,
+        future: F,
+    ) -> ErrTracked<impl Future<Output = anyhow::Result<()>>, anyhow::Error>
+    where
+        F: Future<Output = Result<(), E>>,
+        E: Into<anyhow::Error>,
+    {
+        self.track(async move { future.await.map_err(Into::into) })
+    }
+
+    /// Like [`fail`](Self::fail), for anything that converts into an [`anyhow::Error`].
+    pub fn fail_anyhow(&self, error: impl Into<anyhow::Error>) {
+        self.fail(error.into());
+    }
+}
+
+fn fail<E>(inner: &Inner, slot: &Mutex<Option<E>>, error: E) {
+    let mut slot = slot.lock().unwrap();
+    if slot.is_none() {
+        *slot = Some(error);
+        drop(slot);
+        inner.cancel();
+    }
+}
+
+impl<E> Default for ErrGroup<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F, E> Future for ErrTracked<F, E>
+where
+    F: Future<Output = Result<(), E>>,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = match this.future.poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+        if let Some(worker) = this.worker.take() {
+            // record the error before the worker is released, so `wait` sees it
+            if let Err(error) = result {
+                fail(&worker.inner, this.error, error);
+            }
+        }
+        Poll::Ready(())
+    }
+}
+
+impl<E> Future for ErrWait<E> {
+    type Output = Result<(), E>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.wait).poll(cx) {
+            Poll::Ready(()) => match self.error.lock().unwrap().take() {
+                Some(error) => Poll::Ready(Err(error)),
+                None => Poll::Ready(Ok(())),
+            },
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::task;
+
+    #[async_std::test]
+    async fn first_error_wins() {
+        let group = ErrGroup::new();
+        let cancelled = group.cancelled();
+        let sibling = task::spawn(group.track(async move {
+            cancelled.await;
+            Err("cancelled")
+        }));
+        group.fail("first");
+        task::spawn(group.track(async { Err("second") }));
+        sibling.await;
+
+        assert!(group.is_cancelled());
+        assert_eq!(group.wait().await, Err("first"));
+        assert_eq!(group.wait().await, Ok(()));
+    }
+
+    #[async_std::test]
+    async fn error_type() {
+        let group = WaitGroup::builder().error_type::<u32>().build();
+        task::spawn(group.track(async { Err(404) }));
+        assert_eq!(group.wait().await, Err(404));
+
+        let group: ErrGroup = ErrGroup::new();
+        group.fail("boxed".into());
+        assert_eq!(group.wait().await.unwrap_err().to_string(), "boxed");
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[async_std::test]
+    async fn anyhow() {
+        let group = ErrGroup::<anyhow::Error>::new();
+        task::spawn(group.track_anyhow(async { "x".parse::<u32>().map(drop) }));
+        let result: anyhow::Result<()> = group.wait().await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid digit found in string"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use crate::Worker;
+use pin_project_lite::pin_project;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+pin_project! {
+    /// A tracked future that reports its output to a [`TrackedHandle`].
+    ///
+    /// Created by [`WaitGroup::track_with_handle`](crate::WaitGroup::track_with_handle).
+    pub struct TrackedTask<F, T> {
+        #[pin]
+        future: F,
+        worker: Option<Worker>,
+        shared: Closer<T>,
+    }
+}
+
+/// A handle to join or abort a single [`TrackedTask`].
+///
+/// The group still counts the task until it finishes or observes the abort.
+pub struct TrackedHandle<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// Error returned by a [`TrackedHandle`] whose task was aborted or dropped before it
+/// finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aborted;
+
+struct Shared<T> {
+    output: Option<T>,
+    aborted: bool,
+    // set once the task is dropped
+    closed: bool,
+    task: Option<Waker>,
+    handle: Option<Waker>,
+}
+
+/// Closes the shared state when the task is dropped, so the handle does not wait forever.
+struct Closer<T>(Arc<Mutex<Shared<T>>>);
+
+pub(crate) fn pair<F: Future>(
+    future: F,
+    worker: Worker,
+) -> (TrackedTask<F, F::Output>, TrackedHandle<F::Output>) {
+    let shared = Arc::new(Mutex::new(Shared {
+        output: None,
+        aborted: false,
+        closed: false,
+        task: None,
+        handle: None,
+    }));
+    let task = TrackedTask {
+        future,
+        worker: Some(worker),
+        shared: Closer(shared.clone()),
+    };
+    (task, TrackedHandle { shared })
+}
+
+impl<F> Future for TrackedTask<F, F::Output>
+where
+    F: Future,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if this.worker.is_none() {
+            return Poll::Ready(());
+        }
+        {
+            let mut shared = this.shared.0.lock().unwrap();
+            if shared.aborted {
+                drop(shared);
+                this.worker.take();
+                return Poll::Ready(());
+            }
+            shared.task = Some(cx.waker().clone());
+        }
+        let output = match this.future.poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return Poll::Pending,
+        };
+        let mut shared = this.shared.0.lock().unwrap();
+        shared.output = Some(output);
+        let handle = shared.handle.take();
+        drop(shared);
+        this.worker.take();
+        if let Some(waker) = handle {
+            waker.wake();
+        }
+        Poll::Ready(())
+    }
+}
+
+impl<T> TrackedHandle<T> {
+    /// Stops the task the next time it is polled.
+    ///
+    /// The handle then resolves to [`Aborted`], unless the task already finished.
+    pub fn abort(&self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.aborted = true;
+        let task = shared.task.take();
+        drop(shared);
+        if let Some(waker) = task {
+            waker.wake();
+        }
+    }
+
+    /// Returns `true` if the task has delivered its output or was dropped.
+    pub fn is_finished(&self) -> bool {
+        let shared = self.shared.lock().unwrap();
+        shared.output.is_some() || shared.closed
+    }
+}
+
+impl<T> Future for TrackedHandle<T> {
+    type Output = Result<T, Aborted>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap();
+        if let Some(output) = shared.output.take() {
+            return Poll::Ready(Ok(output));
+        }
+        if shared.closed {
+            return Poll::Ready(Err(Aborted));
+        }
+        shared.handle = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for Closer<T> {
+    fn drop(&mut self) {
+        let mut shared = self.0.lock().unwrap();
+        shared.closed = true;
+        let handle = shared.handle.take();
+        drop(shared);
+        if let Some(waker) = handle {
+            waker.wake();
+        }
+    }
+}
+
+impl fmt::Display for Aborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("task was aborted")
+    }
+}
+
+impl Error for Aborted {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::WaitGroup;
+    use async_std::task;
+    use std::future;
+
+    #[async_std::test]
+    async fn join() {
+        let wg = WaitGroup::new();
+        let (task, handle) = wg.track_with_handle(async { 42 });
+        task::spawn(task);
+        assert_eq!(handle.await, Ok(42));
+        wg.wait().await;
+    }
+
+    #[async_std::test]
+    async fn abort() {
+        let wg = WaitGroup::new();
+        let (task, handle) = wg.track_with_handle(future::pending::<()>());
+        let task = task::spawn(task);
+        assert_eq!(wg.workers(), 1);
+        handle.abort();
+        task.await;
+        assert!(handle.is_finished());
+        assert_eq!(handle.await, Err(Aborted));
+        assert!(wg.is_complete());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{Inner, TrackedFuture, Until, WaitGroup, WaitGroupFuture, Waiter, Worker};
+use hyper::body::{Body, Incoming};
+use hyper::rt::{Read, Write};
+use hyper::server::conn::http1;
+use hyper::service::{HttpService, Service};
+use pin_project_lite::pin_project;
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// A hyper connection that can be shut down gracefully.
+///
+/// This is implemented for hyper's HTTP/1 server connection, implement it for other
+/// connection types to use them with [`WaitGroup::watch_connection`].
+pub trait GracefulConnection: Future {
+    /// Stops accepting new requests, the connection resolves once those in flight are done.
+    fn graceful_shutdown(self: Pin<&mut Self>);
+}
+
+/// A hyper service that holds a worker while each response is pending.
+///
+/// Created by [`WaitGroup::hyper_service`].
+#[derive(Clone)]
+pub struct HyperService<S> {
+    inner: S,
+    group: Arc<Inner>,
+}
+
+pin_project! {
+    /// A connection that holds a worker and shuts down gracefully when the group is cancelled.
+    ///
+    /// Created by [`WaitGroup::watch_connection`].
+    pub struct Watched<C> {
+        #[pin]
+        conn: C,
+        cancelled: Option<WaitGroupFuture>,
+        worker: Option<Worker>,
+    }
+}
+
+impl<S> HyperService<S> {
+    pub(crate) fn new(inner: S, wg: &WaitGroup) -> Self {
+        Self {
+            inner,
+            group: wg.inner.clone(),
+        }
+    }
+
+    /// Gets a handle to wait for the requests in flight.
+    pub fn waiter(&self) -> Waiter {
+        Waiter(self.group.clone())
+    }
+
+    /// Gets the number of requests in flight.
+    pub fn in_flight(&self) -> usize {
+        self.group.workers()
+    }
+}
+
+impl<S, R> Service<R> for HyperService<S>
+where
+    S: Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = TrackedFuture<S::Future>;
+
+    fn call(&self, req: R) -> Self::Future {
+        let worker = self.group.acquire();
+        TrackedFuture::new(self.inner.call(req), worker)
+    }
+}
+
+impl<C> Watched<C> {
+    pub(crate) fn new(conn: C, wg: &WaitGroup) -> Self {
+        Self {
+            conn,
+            cancelled: Some(wg.inner.wait_for(Until::Cancelled)),
+            worker: Some(wg.inner.acquire()),
+        }
+    }
+}
+
+impl<C: GracefulConnection> Future for Watched<C> {
+    type Output = C::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        if let Some(cancelled) = this.cancelled {
+            if Pin::new(cancelled).poll(cx).is_ready() {
+                *this.cancelled = None;
+                this.conn.as_mut().graceful_shutdown();
+            }
+        }
+        let output = match this.conn.poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return Poll::Pending,
+        };
+        this.worker.take();
+        Poll::Ready(output)
+    }
+}
+
+impl<I, B, S> GracefulConnection for http1::Connection<I, S>
+where
+    S: HttpService<Incoming, ResBody = B>,
+    S::Error: Into<Box<dyn Error + Send + Sync>>,
+    I: Read + Write + Unpin,
+    B: Body + 'static,
+    B::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    fn graceful_shutdown(self: Pin<&mut Self>) {
+        http1::Connection::graceful_shutdown(self);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::task;
+    use std::future;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct Conn {
+        // set once the connection is asked to shut down
+        closing: Arc<AtomicBool>,
+    }
+
+    impl Future for Conn {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+            if self.closing.load(Ordering::SeqCst) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    impl GracefulConnection for Conn {
+        fn graceful_shutdown(self: Pin<&mut Self>) {
+            self.closing.store(true, Ordering::SeqCst);
+        }
+    }
+
+    struct Echo;
+
+    impl Service<u32> for Echo {
+        type Response = u32;
+        type Error = ();
+        type Future = future::Ready<Result<u32, ()>>;
+
+        fn call(&self, req: u32) -> Self::Future {
+            future::ready(Ok(req))
+        }
+    }
+
+    #[async_std::test]
+    async fn watch_connection() {
+        let wg = WaitGroup::new();
+        let closing = Arc::new(AtomicBool::new(false));
+        task::spawn(wg.watch_connection(Conn {
+            closing: closing.clone(),
+        }));
+        assert_eq!(wg.workers(), 1);
+
+        wg.cancel();
+        wg.wait().await;
+        assert!(closing.load(Ordering::SeqCst));
+    }
+
+    #[async_std::test]
+    async fn hyper_service() {
+        let wg = WaitGroup::new();
+        let service = wg.hyper_service(Echo);
+        let response = service.call(1);
+        assert_eq!(service.in_flight(), 1);
+        assert_eq!(response.await, Ok(1));
+        service.waiter().wait().await;
+    }
+}
@@ -0,0 +1,113 @@
+use crate::waiters::Waiters;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+/// A latch that opens once it has been counted down a fixed number of times.
+///
+/// Unlike [`WaitGroup`](crate::WaitGroup) there are no worker handles, which suits
+/// completions signaled from callbacks.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use waitgroup::CountdownLatch;
+/// use async_std::task;
+/// # task::block_on(async {
+/// let latch = Arc::new(CountdownLatch::new(3));
+/// for _ in 0..3 {
+///     let latch = latch.clone();
+///     std::thread::spawn(move || latch.count_down());
+/// }
+///
+/// latch.wait().await;
+/// # });
+/// ```
+pub struct CountdownLatch {
+    count: AtomicUsize,
+    waiters: Waiters,
+}
+
+/// Future returned by [`CountdownLatch::wait`].
+pub struct LatchWait<'a> {
+    latch: &'a CountdownLatch,
+    key: Option<usize>,
+}
+
+impl CountdownLatch {
+    /// Creates a latch that opens after `n` calls to [`count_down`](Self::count_down).
+    pub fn new(n: usize) -> Self {
+        Self {
+            count: AtomicUsize::new(n),
+            waiters: Waiters::new(),
+        }
+    }
+
+    /// Decrements the count, opening the latch when it reaches zero.
+    ///
+    /// Counting down an open latch does nothing.
+    pub fn count_down(&self) {
+        let prev = self
+            .count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1));
+        if prev == Ok(1) {
+            self.waiters.wake_all();
+        }
+    }
+
+    /// Gets the remaining count.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the count has reached zero.
+    pub fn is_open(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// Waits for the count to reach zero.
+    pub fn wait(&self) -> LatchWait<'_> {
+        LatchWait {
+            latch: self,
+            key: None,
+        }
+    }
+}
+
+impl Future for LatchWait<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if !this.latch.is_open() {
+            this.latch.waiters.register(&mut this.key, cx.waker(), ());
+            // the latch may have opened before the waker was registered
+            if !this.latch.is_open() {
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(())
+    }
+}
+
+impl Drop for LatchWait<'_> {
+    fn drop(&mut self) {
+        self.latch.waiters.remove(&mut self.key);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[async_std::test]
+    async fn count_down() {
+        let latch = CountdownLatch::new(2);
+        latch.count_down();
+        assert_eq!(latch.count(), 1);
+        latch.count_down();
+        latch.count_down();
+        assert_eq!(latch.count(), 0);
+        latch.wait().await;
+    }
+}
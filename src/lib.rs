@@ -12,7 +12,7 @@
 //!         let w = wg.worker();
 //!         task::spawn(async move {
 //!             // do work...
-//!             drop(w); // drop w means task finished, or just use `let _worker = w;`
+//!             w.done(); // or just drop w, e.g. with `let _worker = w;`
 //!         });
 //!         // 2. waiting nested tasks using `Worker::clone`.
 //!         let w = wg.worker();
@@ -37,114 +37,3716 @@
 //! }
 //! # );
 //! ```
+//!
+//! ## Features
+//!
+//! - `actix-web`: [`WaitGroupMiddleware`] to drain in-flight requests of an actix-web app.
+//! - `anyhow`: conversions for application code based on [`anyhow`](https://docs.rs/anyhow),
+//!   such as [`WaitGroup::wait_anyhow`] and [`ErrGroup::track_anyhow`].
+//! - `async-channel`: a [`Completion`] message per finished worker, see
+//!   [`Builder::completions`].
+//! - `async-std`, `smol`, `tokio`: helpers to spawn tracked tasks on the respective runtime,
+//!   [`WaitGroup::spawn_async_std`], [`WaitGroup::spawn_smol`] and [`WaitGroup::spawn`].
+//!   `async-std` and `tokio` also enable [`WaitGroup::scope`].
+//! - `futures-task`: [`WaitGroup::spawn_with`] to spawn on any executor implementing `Spawn`.
+//! - `futures-util`: [`WaitGroup::track_unordered`] to drive tracked futures concurrently.
+//! - `hyper`: [`WaitGroup::hyper_service`] and [`WaitGroup::watch_connection`] to drain a
+//!   hyper server gracefully.
+//! - `log`: debug and trace messages when a worker is created or finishes and when the group
+//!   drains, prefixed with the group's name, see [`Builder::name`].
+//! - `metrics`: a `waitgroup_active_workers` gauge and a `waitgroup_workers_total` counter
+//!   for each named group, labelled with `group`, see [`Builder::name`].
+//! - `otel`: OpenTelemetry spans for the lifetime of each group and worker, and counters of
+//!   active and finished workers. A worker's span is a child of the current context when the
+//!   worker is created, and links to the span of its group. They use the global providers
+//!   unless set with [`Builder::tracer`] and [`Builder::meter`].
+//! - `prometheus`: [`WaitGroupCollector`] to export the statistics of a group.
+//! - `rayon`: [`WaitGroup::spawn_rayon`] to await CPU-bound jobs running on rayon.
+//! - `signal`: [`Shutdown`] triggered by `SIGINT` or `SIGTERM`, on Unix.
+//! - `tokio-util`: interop with tokio-util's `CancellationToken`, see
+//!   [`Builder::cancellation_token`] and [`WaitGroup::child_token`].
+//! - `tower`: [`WaitGroupLayer`] to drain in-flight requests of a tower service.
+//! - `tracing`: a span per group and per worker, with events when a worker is created or
+//!   finishes and when the group drains, see [`Worker::span`].
 
-use atomic_waker::AtomicWaker;
-use std::future::Future;
+use futures_core::future::FusedFuture;
+use futures_timer::Delay;
+use pin_project_lite::pin_project;
+use registry::{Meta, Registry};
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::future::{Future, IntoFuture};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem;
 use std::pin::Pin;
-use std::sync::{Arc, Weak};
-use std::task::{Context, Poll};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+use waiters::Waiters;
+
+#[cfg(feature = "actix-web")]
+pub use actix::{WaitGroupMiddleware, WaitGroupMiddlewareService};
+pub use barrier::{Arrive, Barrier, BarrierWaitResult};
+pub use builder::{Builder, ErrGroupBuilder};
+#[cfg(feature = "prometheus")]
+pub use collector::WaitGroupCollector;
+#[cfg(feature = "async-channel")]
+pub use completion::Completion;
+pub use drain::{Drain, DrainReport, Escalation};
+pub use errgroup::{ErrGroup, ErrTracked, ErrWait};
+pub use handle::{Aborted, TrackedHandle, TrackedTask};
+#[cfg(feature = "hyper")]
+pub use http::{GracefulConnection, HyperService, Watched};
+pub use latch::{CountdownLatch, LatchWait};
+pub use local::{LocalWait, LocalWaitGroup, LocalWorker};
+pub use observer::WaitGroupObserver;
+pub use phaser::{ArriveAndAwait, Phaser};
+pub use registry::{GroupInfo, WorkerInfo};
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub use scope::{BorrowedScope, Nursery, Scope, ScopeFuture, TaskScope};
+#[cfg(feature = "tower")]
+pub use service::{WaitGroupLayer, WaitGroupService};
+pub use set::{WaitAll, WaitAnyGroup, WaitGroupSet};
+pub use shutdown::{Shutdown, ShutdownWait};
+pub use taskgroup::{Order, TaskFuture, TaskGroup, TaskWait};
+#[cfg(feature = "futures-util")]
+pub use track::TrackUnordered;
+pub use track::{CatchUnwind, Tracked, TrackedFuture, WaitGroupExt, WaitGroupStreamExt};
+pub use values::{ResultWorker, WaitValues};
+
+#[cfg(feature = "actix-web")]
+mod actix;
+mod barrier;
+mod builder;
+#[cfg(feature = "prometheus")]
+mod collector;
+#[cfg(feature = "async-channel")]
+mod completion;
+mod dot;
+mod drain;
+mod errgroup;
+mod handle;
+#[cfg(feature = "hyper")]
+mod http;
+mod latch;
+mod local;
+mod observer;
+#[cfg(feature = "otel")]
+mod otel;
+mod phaser;
+mod registry;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+mod rt;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+mod scope;
+#[cfg(feature = "tower")]
+mod service;
+mod set;
+mod shutdown;
+mod taskgroup;
+mod track;
+mod values;
+mod waiters;
 
 pub struct WaitGroup {
     inner: Arc<Inner>,
+    // registration used by `poll_wait`, and the round it waits for
+    key: Option<usize>,
+    polled: Option<Until>,
+}
+
+pub struct Worker {
+    inner: Arc<Inner>,
+    // held to keep the epoch alive until the worker finishes
+    epoch: Arc<Epoch>,
+    id: u64,
+    name: Option<Arc<str>>,
+    tag: Option<&'static str>,
+    meta: Option<Meta>,
+    // reported by the registry, which only lists the worker once it is named or touched
+    created: Instant,
+    deadline: Option<Instant>,
+    // number of units the worker accounts for
+    weight: usize,
+    // whether the worker has an entry in the registry
+    registered: bool,
+    released: bool,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "otel")]
+    otel_span: opentelemetry::global::BoxedSpan,
+}
+
+/// A worker for a synchronous scope, created by [`WaitGroup::enter`].
+///
+/// The group waits for the guard until it is dropped. A guard cannot be sent to another
+/// thread, use a [`Worker`] for that.
+///
+/// ```compile_fail
+/// let wg = waitgroup::WaitGroup::new();
+/// let guard = wg.enter();
+/// std::thread::spawn(move || drop(guard));
+/// ```
+pub struct Guard {
+    _worker: Worker,
+    _not_send: PhantomData<*const ()>,
 }
 
+/// A handle to a group that does not count as a worker.
+///
+/// Created by [`Worker::downgrade`].
+#[derive(Clone)]
+pub struct WeakWorker(Weak<Inner>);
+
+/// The cancellation signal of a group, for graceful shutdown.
+///
+/// Raising it does not release any worker, waiting on the group still drains everyone.
+///
+/// ```rust
+/// use waitgroup::WaitGroup;
+/// use async_std::task;
+/// # task::block_on(async {
+/// let wg = WaitGroup::new();
+/// for _ in 0..10 {
+///     let w = wg.worker();
+///     let token = w.cancel_token();
+///     task::spawn(async move {
+///         token.cancelled().await;
+///         // clean up...
+///         drop(w);
+///     });
+/// }
+///
+/// wg.cancel();
+/// wg.wait().await;
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct CancelToken(Arc<Inner>);
+
+/// The half of a split [`WaitGroup`] that creates workers.
 #[derive(Clone)]
-pub struct Worker(Arc<Inner>);
+pub struct Spawner(Arc<Inner>);
+
+/// The half of a split [`WaitGroup`] that waits for workers.
+pub struct Waiter(Arc<Inner>);
 
+/// Future returned by [`WaitGroup::wait`].
+///
+/// It can be cloned so that several tasks are all woken when the group completes.
+///
+/// # Cancellation safety
+///
+/// The future is cancellation safe, so it can be dropped and created again in every
+/// iteration of a `select!` loop. It registers its waker on poll and checks the group again
+/// afterwards, so a completion between the two is never missed, and dropping it releases
+/// the registration. A future created after the group completed resolves right away.
 pub struct WaitGroupFuture {
-    inner: Weak<Inner>,
+    inner: Arc<Inner>,
+    until: Until,
+    key: Option<usize>,
+    // fires at the next deadline if the group releases overdue workers
+    delay: Option<Delay>,
+    // registration with the group's cancellation token, which can be cancelled by a parent
+    #[cfg(feature = "tokio-util")]
+    token: Option<Pin<Box<tokio_util::sync::WaitForCancellationFutureOwned>>>,
+    done: bool,
 }
 
-impl WaitGroupFuture {
-    /// Gets the number of active workers.
-    pub fn workers(&self) -> usize {
-        Weak::strong_count(&self.inner)
+#[derive(Clone)]
+enum Until {
+    Drained(usize),
+    Below(usize),
+    Completed(usize),
+    Checkpoint(Weak<Epoch>),
+    Tag(&'static str),
+    Cancelled,
+}
+
+impl Until {
+    fn is_reached(&self, inner: &Inner) -> bool {
+        match self {
+            Until::Drained(generation) => {
+                inner.count.load(Ordering::SeqCst) == 0
+                    || inner.generation.load(Ordering::SeqCst) != *generation
+            }
+            Until::Below(n) => inner.count.load(Ordering::SeqCst) < *n,
+            Until::Completed(n) => inner.completed.load(Ordering::SeqCst) >= *n,
+            Until::Checkpoint(epoch) => epoch.strong_count() == 0,
+            Until::Tag(tag) => inner.registry.lock().unwrap().tagged(tag) == 0,
+            Until::Cancelled => inner.is_cancelled(),
+        }
+    }
+
+    /// Makes sure `release` wakes waiters that wait for this condition.
+    fn arm(&self, inner: &Inner) {
+        match self {
+            Until::Drained(_) | Until::Checkpoint(_) | Until::Tag(_) | Until::Cancelled => {}
+            Until::Below(n) => {
+                inner.threshold.fetch_max(*n, Ordering::SeqCst);
+            }
+            Until::Completed(_) => inner.watch_completed.store(true, Ordering::SeqCst),
+        }
     }
 }
 
-struct Inner {
-    waker: AtomicWaker,
+/// The worker a subgroup holds in its parent group.
+///
+/// The completion hook runs right before the worker is released, either when the subgroup
+/// drains or when it is dropped.
+struct Parent {
+    on_complete: Option<Box<dyn FnOnce() + Send>>,
+    // only held, dropped after `drop` ran the hook
+    _worker: Worker,
 }
 
-impl Drop for Inner {
+impl Drop for Parent {
     fn drop(&mut self) {
-        self.waker.wake();
+        if let Some(hook) = self.on_complete.take() {
+            hook();
+        }
     }
 }
 
-impl WaitGroup {
-    pub fn new() -> Self {
-        Self {
-            inner: Arc::new(Inner {
-                waker: AtomicWaker::new(),
-            }),
+/// The workers created between two checkpoints.
+///
+/// Every worker holds its epoch, and every epoch holds the one after it, so an epoch is
+/// dropped once its own workers and those of all earlier epochs have finished.
+struct Epoch {
+    group: Weak<Inner>,
+    next: Mutex<Option<Arc<Epoch>>>,
+    // shared with the epochs up to the next detach
+    round: Arc<Round>,
+}
+
+/// The units of the workers created between two detaches, see [`Escalation::Detach`].
+///
+/// Workers join the round of their epoch and leave it when they finish, so detaching a round
+/// releases exactly the units whose workers have not finished yet.
+#[derive(Debug, Default)]
+struct Round {
+    // the number of units, with the highest bit set once the round was detached
+    units: AtomicUsize,
+}
+
+const DETACHED: usize = 1 << (usize::BITS - 1);
+
+impl Round {
+    /// Adds `n` units, returning `false` if the round was detached.
+    fn join(&self, n: usize) -> bool {
+        self.units
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |units| {
+                (units & DETACHED == 0).then(|| units + n)
+            })
+            .is_ok()
+    }
+
+    /// Removes `n` units, returning `false` if the round was detached and they were already
+    /// released.
+    fn leave(&self, n: usize) -> bool {
+        self.units
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |units| {
+                (units & DETACHED == 0).then(|| units - n)
+            })
+            .is_ok()
+    }
+
+    /// Detaches the round, returning the units still in it.
+    fn detach(&self) -> usize {
+        self.units.fetch_or(DETACHED, Ordering::SeqCst) & !DETACHED
+    }
+}
+
+impl Drop for Epoch {
+    fn drop(&mut self) {
+        // unlink drained epochs in a loop, a long chain would overflow the stack otherwise
+        let mut next = self.next.get_mut().unwrap().take();
+        while let Some(epoch) = next {
+            next = match Arc::try_unwrap(epoch) {
+                Ok(mut epoch) => epoch.next.get_mut().unwrap().take(),
+                Err(_) => None,
+            };
         }
+        if let Some(inner) = self.group.upgrade() {
+            inner
+                .waiters
+                .wake(|until| matches!(until, Until::Checkpoint(_)) && until.is_reached(&inner));
+        }
+    }
+}
+
+impl WaitGroupFuture {
+    /// Returns `true` if a drained strict group never had any workers.
+    fn never_started(&self) -> bool {
+        self.inner.strict
+            && matches!(self.until, Until::Drained(_))
+            && self.inner.total.load(Ordering::Relaxed) == 0
     }
 
-    pub fn worker(&self) -> Worker {
-        Worker(self.inner.clone())
+    /// Releases expired workers and schedules a wakeup for the next deadline.
+    fn poll_overdue(&mut self, cx: &mut Context<'_>) {
+        while let Some(next) = self.inner.expire_overdue() {
+            let delay = self.delay.get_or_insert_with(|| Delay::new(Duration::ZERO));
+            delay.reset(next.saturating_duration_since(Instant::now()));
+            if Pin::new(delay).poll(cx).is_pending() {
+                return;
+            }
+        }
+        self.delay = None;
     }
 
     /// Gets the number of active workers.
     pub fn workers(&self) -> usize {
-        Arc::strong_count(&self.inner) - 1
+        self.inner.workers()
     }
 
-    pub fn wait(self) -> WaitGroupFuture {
-        WaitGroupFuture {
-            inner: Arc::downgrade(&self.inner),
+    /// Returns `true` if all workers have finished.
+    pub fn is_complete(&self) -> bool {
+        self.workers() == 0
+    }
+
+    /// Waits for all workers to finish, giving up after `dur`.
+    ///
+    /// The timer runs on a background thread, so this works with any executor.
+    pub fn timeout(self, dur: Duration) -> Timeout {
+        Timeout {
+            wait: self,
+            delay: Delay::new(dur),
         }
     }
+
+    /// Waits for all workers to finish, giving up at `deadline`.
+    pub fn timeout_at(self, deadline: Instant) -> Timeout {
+        self.timeout(deadline.saturating_duration_since(Instant::now()))
+    }
 }
 
-impl Default for WaitGroup {
-    fn default() -> Self {
-        Self::new()
+/// Future returned by [`WaitGroup::acquire`].
+pub struct Acquire {
+    inner: Arc<Inner>,
+    key: Option<usize>,
+}
+
+/// Future returned by [`WaitGroup::wait_timeout`] and [`WaitGroup::wait_until`].
+pub struct Timeout {
+    wait: WaitGroupFuture,
+    delay: Delay,
+}
+
+pin_project! {
+    /// Future returned by [`WaitGroup::wait_or`].
+    pub struct WaitOr<C> {
+        wait: WaitGroupFuture,
+        #[pin]
+        cancel: C,
     }
 }
 
-/*
-IntoFuture tracking issue: https://github.com/rust-lang/rust/issues/67644
-impl IntoFuture for WaitGroup {
-    type Output = ();
-    type Future = WaitGroupFuture;
+/// Output of [`WaitOr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitOutcome {
+    /// All workers finished.
+    Completed,
+    /// The cancellation future resolved first.
+    Cancelled {
+        /// The number of workers that were still active.
+        remaining: usize,
+    },
+}
+
+/// Future returned by [`WaitGroup::wait_with_stats`].
+pub struct WaitWithStats {
+    wait: WaitGroupFuture,
+}
+
+/// An error reported by [`Worker::fail`].
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Future returned by [`WaitGroup::wait_result`].
+pub struct WaitResult {
+    wait: WaitGroupFuture,
+}
+
+/// Future returned by [`WaitGroup::wait_anyhow`].
+#[cfg(feature = "anyhow")]
+pub struct WaitAnyhow {
+    wait: WaitResult,
+}
+
+/// Future returned by [`WaitGroup::wait_errors`].
+pub struct WaitErrors {
+    wait: WaitGroupFuture,
+}
+
+/// The error reported for a worker that finished by panicking.
+#[derive(Debug, Clone)]
+pub struct Panicked {
+    message: Option<String>,
+}
 
-    fn into_future(self) -> Self::Future {
-        WaitGroupFuture { inner: Arc::downgrade(&self.inner) }
+impl Panicked {
+    /// Gets the panic message, if it is known.
+    ///
+    /// The message of a panic that unwinds through a worker is not available, only that of
+    /// one caught by [`WaitGroup::track_catching`].
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
     }
 }
-*/
 
-impl Future for WaitGroupFuture {
-    type Output = ();
+impl fmt::Display for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "worker panicked: {}", message),
+            None => f.write_str("worker panicked"),
+        }
+    }
+}
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.inner.upgrade() {
-            Some(inner) => {
-                inner.waker.register(cx.waker());
-                Poll::Pending
+impl Error for Panicked {}
+
+/// The error [`wait_result`](WaitGroup::wait_result) resolves to when a
+/// [`strict`](Builder::strict) group never had any workers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoWorkers;
+
+impl fmt::Display for NoWorkers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("waited on a strict WaitGroup that never had any workers")
+    }
+}
+
+impl Error for NoWorkers {}
+
+/// The errors reported by the workers of a group, in the order they were reported.
+#[derive(Debug)]
+pub struct Errors {
+    errors: Vec<BoxError>,
+}
+
+impl Errors {
+    /// Gets the number of errors.
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns `true` if there are no errors.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &BoxError> {
+        self.errors.iter()
+    }
+}
+
+impl IntoIterator for Errors {
+    type Item = BoxError;
+    type IntoIter = std::vec::IntoIter<BoxError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl fmt::Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} workers failed", self.errors.len())?;
+        if let Some(first) = self.errors.first() {
+            write!(f, ", the first with: {}", first)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Errors {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.errors.first().map(|error| &**error as _)
+    }
+}
+
+/// Statistics about a completed [`WaitGroup`].
+#[derive(Debug, Clone)]
+pub struct Stats {
+    elapsed: Duration,
+    peak_workers: usize,
+    total_workers: usize,
+    overruns: usize,
+    panicked: usize,
+}
+
+impl Stats {
+    /// Gets the time from creating the group until all workers finished.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Gets the maximum number of workers that were active at the same time.
+    pub fn peak_workers(&self) -> usize {
+        self.peak_workers
+    }
+
+    /// Gets the number of workers created over the lifetime of the group.
+    pub fn total_workers(&self) -> usize {
+        self.total_workers
+    }
+
+    /// Gets the number of workers that were still active after their deadline.
+    pub fn overruns(&self) -> usize {
+        self.overruns
+    }
+
+    /// Gets the number of workers that finished by panicking, see [`WaitGroup::panicked`].
+    pub fn panicked(&self) -> usize {
+        self.panicked
+    }
+}
+
+/// Error returned when a [`Timeout`] elapses before all workers finish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed {
+    workers: usize,
+}
+
+impl Elapsed {
+    /// Gets the number of workers that were still active when the deadline passed.
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+}
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "deadline has elapsed with {} workers still active",
+            self.workers
+        )
+    }
+}
+
+impl Error for Elapsed {}
+
+struct Inner {
+    count: AtomicUsize,
+    peak: AtomicUsize,
+    total: AtomicUsize,
+    completed: AtomicUsize,
+    // workers still active after their deadline
+    overruns: AtomicUsize,
+    // workers that finished by panicking
+    panicked: AtomicUsize,
+    // workers that reported their outcome
+    succeeded: AtomicUsize,
+    failed: AtomicUsize,
+    // errors reported by `Worker::fail`, only the first unless `collect_errors` is set
+    errors: Mutex<Vec<BoxError>>,
+    collect_errors: bool,
+    cancel_on_panic: bool,
+    stages: drain::Stages,
+    // groups cancelled together with this one
+    children: Mutex<Vec<Weak<Inner>>>,
+    // the worker held in the parent group by a subgroup until it drains
+    parent: Mutex<Option<Parent>>,
+    name: Option<Arc<str>>,
+    // cancelled together with `cancelled`, and by the parent token from the builder
+    #[cfg(feature = "tokio-util")]
+    token: tokio_util::sync::CancellationToken,
+    #[cfg(feature = "async-channel")]
+    completions: Option<async_channel::Sender<Completion>>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "otel")]
+    telemetry: otel::Telemetry,
+    observer: Option<observer::Observer>,
+    // values delivered by `ResultWorker::complete`, a `Vec<T>` for every type `T`
+    values: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
+    // units added by `WaitGroup::add`, oldest first, with the epoch they joined
+    loose: Mutex<VecDeque<(Arc<Epoch>, usize)>>,
+    // bumped every time the count drops to zero
+    generation: AtomicUsize,
+    created: Instant,
+    // release only wakes waiters once the count drops below this value,
+    // or on every release if someone waits for completions
+    threshold: AtomicUsize,
+    watch_completed: AtomicBool,
+    cancelled: AtomicBool,
+    epoch: Mutex<Arc<Epoch>>,
+    // run once the next time the count drops to zero
+    on_drain: Mutex<Vec<Box<dyn FnOnce() + Send>>>,
+    next_id: AtomicU64,
+    registry: Mutex<Registry>,
+    strict: bool,
+    // `usize::MAX` if the group is unbounded
+    max_workers: usize,
+    release_overdue: bool,
+    waiters: Waiters<Until>,
+}
+
+impl Inner {
+    fn add(&self, n: usize) {
+        let count = self.count.fetch_add(n, Ordering::Relaxed) + n;
+        self.added(n, count);
+    }
+
+    fn added(&self, n: usize, count: usize) {
+        self.peak.fetch_max(count, Ordering::Relaxed);
+        self.total.fetch_add(n, Ordering::Relaxed);
+        #[cfg(feature = "otel")]
+        self.telemetry.added(n);
+        #[cfg(feature = "metrics")]
+        if let Some(name) = &self.name {
+            let group = name.to_string();
+            metrics::gauge!("waitgroup_active_workers", "group" => group.clone())
+                .increment(n as f64);
+            metrics::counter!("waitgroup_workers_total", "group" => group).increment(n as u64);
+        }
+    }
+
+    fn acquire(self: &Arc<Self>) -> Worker {
+        self.add(1);
+        self.handle(self.epoch.lock().unwrap().clone(), 1)
+    }
+
+    fn acquire_batch(self: &Arc<Self>, n: usize) -> Vec<Worker> {
+        self.add(n);
+        let epoch = self.epoch.lock().unwrap().clone();
+        (0..n).map(|_| self.handle(epoch.clone(), 1)).collect()
+    }
+
+    /// Creates a worker if that keeps the count within `max_workers`.
+    fn try_acquire(self: &Arc<Self>) -> Option<Worker> {
+        let max = self.max_workers;
+        let count = self
+            .count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < max).then(|| n + 1)
+            })
+            .ok()?
+            + 1;
+        self.added(1, count);
+        Some(self.handle(self.epoch.lock().unwrap().clone(), 1))
+    }
+
+    /// Creates a worker accounting for `weight` units.
+    fn acquire_weighted(self: &Arc<Self>, weight: usize) -> Worker {
+        self.add(weight);
+        self.handle(self.epoch.lock().unwrap().clone(), weight)
+    }
+
+    /// Adds `weight` units to the round of `epoch`, or of the current epoch if it was detached.
+    fn join(&self, mut epoch: Arc<Epoch>, weight: usize) -> Arc<Epoch> {
+        // a detached epoch no longer takes workers, the current one always does eventually
+        while !epoch.round.join(weight) {
+            epoch = self.epoch.lock().unwrap().clone();
+        }
+        epoch
+    }
+
+    /// Creates a handle for `weight` units that have already been added to the count.
+    fn handle(self: &Arc<Self>, epoch: Arc<Epoch>, weight: usize) -> Worker {
+        let epoch = self.join(epoch, weight);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut worker = self.handle_as(epoch, id);
+        worker.weight = weight;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(parent: &worker.span, "worker created");
+        #[cfg(feature = "log")]
+        log::trace!("{}: worker {} created", self.label(), id);
+        if let Some(observer) = &self.observer {
+            observer.0.on_worker_created(id);
+        }
+        worker
+    }
+
+    /// Creates a handle that stands in for the worker `id`, without reporting a new worker.
+    fn handle_as(self: &Arc<Self>, epoch: Arc<Epoch>, id: u64) -> Worker {
+        #[cfg(feature = "tracing")]
+        let span = {
+            use tracing::field::Empty;
+            tracing::trace_span!(parent: &self.span, "worker", id, name = Empty, tag = Empty)
+        };
+        Worker {
+            inner: self.clone(),
+            epoch,
+            id,
+            name: None,
+            tag: None,
+            meta: None,
+            created: Instant::now(),
+            deadline: None,
+            weight: 1,
+            registered: false,
+            released: false,
+            #[cfg(feature = "tracing")]
+            span,
+            #[cfg(feature = "otel")]
+            otel_span: self.telemetry.worker_span(id),
+        }
+    }
+
+    /// Gets the name the group's log messages start with.
+    #[cfg(feature = "log")]
+    fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or("waitgroup")
+    }
+
+    /// Adds the worker to the registry so that it shows up in `WaitGroup::pending`.
+    fn register(&self, worker: &mut Worker) {
+        self.registry.lock().unwrap().insert(WorkerInfo {
+            id: worker.id,
+            name: worker.name.clone(),
+            tag: worker.tag,
+            meta: worker.meta.clone(),
+            created: worker.created,
+            last_seen: worker.created,
+            deadline: worker.deadline,
+            weight: worker.weight,
+            expired: false,
+            round: worker.epoch.round.clone(),
+        });
+        worker.registered = true;
+        #[cfg(feature = "tracing")]
+        {
+            worker.span.record("name", worker.name.as_deref());
+            worker.span.record("tag", worker.tag);
+        }
+    }
+
+    fn wait(self: &Arc<Self>) -> WaitGroupFuture {
+        self.wait_for(self.drained())
+    }
+
+    /// Gets the condition for the current round of workers to drain.
+    fn drained(&self) -> Until {
+        Until::Drained(self.generation.load(Ordering::SeqCst))
+    }
+
+    fn workers(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+
+    fn wait_for(self: &Arc<Self>, until: Until) -> WaitGroupFuture {
+        WaitGroupFuture {
+            inner: self.clone(),
+            until,
+            key: None,
+            delay: None,
+            #[cfg(feature = "tokio-util")]
+            token: None,
+            done: false,
+        }
+    }
+
+    fn poll_for(&self, until: &Until, key: &mut Option<usize>, cx: &mut Context<'_>) -> Poll<()> {
+        if !until.is_reached(self) {
+            until.arm(self);
+            self.waiters.register(key, cx.waker(), until.clone());
+            // a worker may have finished before the waker was registered
+            if !until.is_reached(self) {
+                return Poll::Pending;
             }
-            None => Poll::Ready(()),
+        }
+        self.waiters.remove(key);
+        Poll::Ready(())
+    }
+
+    /// Removes `n` from the count, `completed` tells whether they count as finished work.
+    fn release(&self, n: usize, completed: bool) -> usize {
+        let count = self.count.fetch_sub(n, Ordering::SeqCst) - n;
+        self.released(n, count, completed);
+        count
+    }
+
+    fn released(&self, n: usize, count: usize, completed: bool) {
+        if completed {
+            self.completed.fetch_add(n, Ordering::SeqCst);
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(name) = &self.name {
+            metrics::gauge!("waitgroup_active_workers", "group" => name.to_string())
+                .decrement(n as f64);
+        }
+        #[cfg(feature = "otel")]
+        self.telemetry.released(n, count, completed);
+        if count == 0 {
+            self.generation.fetch_add(1, Ordering::SeqCst);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(parent: &self.span, "group drained");
+            #[cfg(feature = "log")]
+            log::debug!("{}: drained", self.label());
+            if let Some(observer) = &self.observer {
+                observer.0.on_complete();
+            }
+            let hooks = mem::take(&mut *self.on_drain.lock().unwrap());
+            for hook in hooks {
+                hook();
+            }
+            // before waking, so the parent has counted the subgroup out once its waiters resume
+            let parent = self.parent.lock().unwrap().take();
+            drop(parent);
+        }
+        if count < self.threshold.load(Ordering::SeqCst)
+            || self.watch_completed.load(Ordering::SeqCst)
+        {
+            self.waiters.wake(|until| until.is_reached(self));
+        }
+    }
+
+    /// Releases the units of expired workers, returning the next deadline to check.
+    fn expire_overdue(&self) -> Option<Instant> {
+        let expiry = self.registry.lock().unwrap().expire(Instant::now());
+        if expiry.workers > 0 {
+            self.overruns.fetch_add(expiry.workers, Ordering::Relaxed);
+        }
+        if expiry.units > 0 {
+            self.release(expiry.units, false);
+        }
+        expiry.next
+    }
+
+    /// Starts a new epoch in `round`, returning the previous one.
+    fn next_epoch(self: &Arc<Self>, round: Option<Arc<Round>>) -> Arc<Epoch> {
+        let mut current = self.epoch.lock().unwrap();
+        let next = Arc::new(Epoch {
+            group: Arc::downgrade(self),
+            next: Mutex::new(None),
+            round: round.unwrap_or_else(|| current.round.clone()),
+        });
+        *current.next.lock().unwrap() = Some(next.clone());
+        mem::replace(&mut *current, next)
+    }
+
+    /// Removes every worker created so far from the count and the registry.
+    ///
+    /// Dropping a detached worker later has no effect on the group.
+    fn detach_all(self: &Arc<Self>) {
+        let round = self.next_epoch(Some(Arc::default())).round.clone();
+        let units = round.detach();
+        let mut registry = self.registry.lock().unwrap();
+        registry.detach(&round);
+        drop(registry);
+        self.waiters
+            .wake(|until| matches!(until, Until::Tag(_)) && until.is_reached(self));
+        if units > 0 {
+            self.release(units, false);
+        }
+    }
+
+    fn report(&self, error: BoxError) {
+        let mut errors = self.errors.lock().unwrap();
+        if errors.is_empty() || self.collect_errors {
+            errors.push(error);
+        }
+    }
+
+    fn record_panic(&self, message: Option<String>) {
+        self.panicked.fetch_add(1, Ordering::Relaxed);
+        self.report(Box::new(Panicked { message }));
+        if self.cancel_on_panic {
+            self.cancel();
+        }
+    }
+
+    /// Raises the cancellation signal, returning `false` if it was already raised.
+    fn cancel(&self) -> bool {
+        if self.cancelled.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        #[cfg(feature = "tokio-util")]
+        self.token.cancel();
+        self.waiters.wake(|until| matches!(until, Until::Cancelled));
+        // kept after cancelling, so the hierarchy still shows up in `dump_dot`
+        let children = self.children.lock().unwrap().clone();
+        for child in children.iter().filter_map(Weak::upgrade) {
+            child.cancel();
+        }
+        true
+    }
+
+    fn link_child(&self, child: &Arc<Inner>) {
+        let mut children = self.children.lock().unwrap();
+        // forget the children that are gone, so a long-lived parent does not grow
+        children.retain(|child| child.strong_count() > 0);
+        children.push(Arc::downgrade(child));
+        if self.is_cancelled() {
+            drop(children);
+            child.cancel();
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        #[cfg(feature = "tokio-util")]
+        if self.token.is_cancelled() {
+            return true;
+        }
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Runs `hook` once the group is drained, right away if it already is.
+    fn on_drain(&self, hook: impl FnOnce() + Send + 'static) {
+        let mut hooks = self.on_drain.lock().unwrap();
+        if self.count.load(Ordering::SeqCst) == 0 {
+            drop(hooks);
+            hook();
+        } else {
+            hooks.push(Box::new(hook));
         }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use async_std::task;
+impl WaitGroup {
+    pub fn new() -> Self {
+        Builder::new().build()
+    }
 
-    #[async_std::test]
-    async fn smoke() {
-        let wg = WaitGroup::new();
+    pub fn builder() -> Builder {
+        Builder::new()
+    }
 
-        for _ in 0..100 {
-            let w = wg.worker();
-            task::spawn(async move {
-                drop(w);
-            });
+    /// Creates a group listed in [`named_groups`](Self::named_groups) under `name`.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    ///
+    /// let wg = WaitGroup::named("http-inflight");
+    /// let _w = wg.worker();
+    /// for group in WaitGroup::named_groups() {
+    ///     println!("{}: {} active", group.name(), group.workers());
+    /// }
+    /// ```
+    pub fn named(name: impl Into<Arc<str>>) -> Self {
+        Builder::new().name(name).build()
+    }
+
+    /// Creates a group that reports the lifecycle of its workers to `observer`.
+    ///
+    /// ```rust
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use waitgroup::{WaitGroup, WaitGroupObserver};
+    ///
+    /// struct Created(AtomicUsize);
+    ///
+    /// impl WaitGroupObserver for &'static Created {
+    ///     fn on_worker_created(&self, _id: u64) {
+    ///         self.0.fetch_add(1, Ordering::Relaxed);
+    ///     }
+    /// }
+    ///
+    /// static CREATED: Created = Created(AtomicUsize::new(0));
+    /// let wg = WaitGroup::with_observer(&CREATED);
+    /// drop(wg.worker());
+    /// assert_eq!(CREATED.0.load(Ordering::Relaxed), 1);
+    /// ```
+    pub fn with_observer(observer: impl WaitGroupObserver) -> Self {
+        Builder::new().observer(observer).build()
+    }
+
+    /// Renders the group, its linked child groups such as [`subgroup`](Self::subgroup)s, and
+    /// the workers listed in [`pending`](Self::pending) as a Graphviz DOT graph.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    ///
+    /// let wg = WaitGroup::named("server");
+    /// let stage = wg.subgroup();
+    /// let _w = stage.named_worker("fetch");
+    /// // render with `dot -Tsvg`
+    /// println!("{}", wg.dump_dot());
+    /// ```
+    pub fn dump_dot(&self) -> String {
+        dot::dump(&self.inner)
+    }
+
+    /// Gets the name of the group, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name.as_deref()
+    }
+
+    /// Lists the named groups of the process that are still alive.
+    pub fn named_groups() -> Vec<GroupInfo> {
+        registry::named_groups()
+    }
+
+    /// Creates a worker.
+    ///
+    /// This ignores the [`max_workers`](Builder::max_workers) limit, use
+    /// [`acquire`](Self::acquire) to respect it.
+    pub fn worker(&self) -> Worker {
+        self.inner.acquire()
+    }
+
+    /// Creates a worker once fewer than [`max_workers`](Builder::max_workers) are active.
+    ///
+    /// The future resolves right away if the group is unbounded.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// use async_std::task;
+    /// # task::block_on(async {
+    /// let wg = WaitGroup::builder().max_workers(4).build();
+    /// for _ in 0..100 {
+    ///     // at most 4 tasks run at the same time
+    ///     let w = wg.acquire().await;
+    ///     task::spawn(async move {
+    ///         // do work...
+    ///         drop(w);
+    ///     });
+    /// }
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    pub fn acquire(&self) -> Acquire {
+        Acquire {
+            inner: self.inner.clone(),
+            key: None,
+        }
+    }
+
+    /// Wraps `f` in a closure that holds a worker until it has run, even if it panics.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    ///
+    /// let wg = WaitGroup::new();
+    /// std::thread::spawn(wg.wrap_fn(|| {
+    ///     // do blocking work...
+    /// }));
+    /// wg.wait_blocking();
+    /// ```
+    pub fn wrap_fn<F, R>(&self, f: F) -> impl FnOnce() -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let worker = self.inner.acquire();
+        move || {
+            let _worker = worker;
+            f()
         }
+    }
 
-        wg.wait().await;
+    /// Wraps `future` so that it holds a worker until it completes or is dropped.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// use async_std::task;
+    /// # task::block_on(async {
+    /// let wg = WaitGroup::new();
+    /// task::spawn(wg.track(async {
+    ///     // do work...
+    /// }));
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    pub fn track<F: Future>(&self, future: F) -> TrackedFuture<F> {
+        TrackedFuture::new(future, self.inner.acquire())
+    }
+
+    /// Spawns `future` on the current tokio runtime, tracked by a worker of this group.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let wg = WaitGroup::new();
+    /// let handle = wg.spawn(async { 1 + 1 });
+    /// wg.wait().await;
+    /// assert_eq!(handle.await.unwrap(), 2);
+    /// # });
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn spawn<F>(&self, future: F) -> tokio::task::JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        tokio::spawn(self.track(future))
+    }
+
+    /// Like [`spawn`](Self::spawn), but spawns into `set`.
+    ///
+    /// The task counts towards both the join set and this group, which may also track work
+    /// outside of tokio. Aborting the task, or dropping the set, releases its worker.
+    ///
+    /// ```rust
+    /// use tokio::task::JoinSet;
+    /// use waitgroup::WaitGroup;
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let wg = WaitGroup::new();
+    /// let mut set = JoinSet::new();
+    /// for i in 0..10 {
+    ///     wg.spawn_in(&mut set, async move { i });
+    /// }
+    /// let blocking = wg.worker();
+    /// std::thread::spawn(move || drop(blocking));
+    ///
+    /// // the tasks of the set and the thread
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn spawn_in<F>(
+        &self,
+        set: &mut tokio::task::JoinSet<F::Output>,
+        future: F,
+    ) -> tokio::task::AbortHandle
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        set.spawn(self.track(future))
+    }
+
+    /// Like [`spawn`](Self::spawn), for `!Send` futures, with `tokio::task::spawn_local`.
+    ///
+    /// The same works on thread-per-core runtimes such as monoio or glommio by passing
+    /// [`track`](Self::track)ed futures to their own spawn function, the worker itself is
+    /// `Send` and can be released from any thread.
+    ///
+    /// ```rust
+    /// use std::rc::Rc;
+    /// use waitgroup::WaitGroup;
+    /// # let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// # tokio::task::LocalSet::new().block_on(&rt, async {
+    /// let wg = WaitGroup::new();
+    /// let shared = Rc::new(42);
+    /// wg.spawn_local(async move {
+    ///     assert_eq!(*shared, 42);
+    /// });
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a `tokio::task::LocalSet`.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_local<F>(&self, future: F) -> tokio::task::JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        tokio::task::spawn_local(self.track(future))
+    }
+
+    /// Spawns `future` through any executor implementing the `futures` `Spawn` trait, tracked
+    /// by a worker of this group.
+    ///
+    /// ```rust
+    /// use futures_executor::ThreadPool;
+    /// use waitgroup::WaitGroup;
+    /// # futures_executor::block_on(async {
+    /// let pool = ThreadPool::new().unwrap();
+    /// let wg = WaitGroup::new();
+    /// for _ in 0..10 {
+    ///     wg.spawn_with(&pool, async {
+    ///         // do work...
+    ///     })
+    ///     .unwrap();
+    /// }
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    #[cfg(feature = "futures-task")]
+    pub fn spawn_with<S, F>(&self, spawner: &S, future: F) -> Result<(), futures_task::SpawnError>
+    where
+        S: futures_task::Spawn + ?Sized,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        spawner.spawn_obj(futures_task::FutureObj::new(Box::new(self.track(future))))
+    }
+
+    /// Wraps a hyper service so that every request holds a worker of this group until its
+    /// response is ready.
+    #[cfg(feature = "hyper")]
+    pub fn hyper_service<S>(&self, service: S) -> HyperService<S> {
+        HyperService::new(service, self)
+    }
+
+    /// Tracks a hyper connection with a worker of this group, and shuts it down gracefully
+    /// once the group is cancelled.
+    ///
+    /// Combined with [`Shutdown`], stopping a server comes down to one call that stops every
+    /// connection from taking new requests and waits for those in flight:
+    ///
+    /// ```rust
+    /// use waitgroup::{GracefulConnection, Shutdown};
+    /// use std::time::Duration;
+    ///
+    /// fn serve<C>(shutdown: &Shutdown, conn: C)
+    /// where
+    ///     C: GracefulConnection + Send + 'static,
+    ///     C::Output: Send,
+    /// {
+    ///     // e.g. `http1::Builder::new().serve_connection(io, service)`
+    ///     async_std::task::spawn(shutdown.group().watch_connection(conn));
+    /// }
+    ///
+    /// # async_std::task::block_on(async {
+    /// let shutdown = Shutdown::new().timeout(Duration::from_secs(30));
+    /// // accept connections and `serve` them...
+    /// let report = shutdown.shutdown().await;
+    /// assert!(report.is_complete());
+    /// # });
+    /// ```
+    #[cfg(feature = "hyper")]
+    pub fn watch_connection<C: GracefulConnection>(&self, conn: C) -> Watched<C> {
+        Watched::new(conn, self)
+    }
+
+    /// Like [`spawn`](Self::spawn), with `async_std::task::spawn`.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// # async_std::task::block_on(async {
+    /// let wg = WaitGroup::new();
+    /// for _ in 0..100 {
+    ///     wg.spawn_async_std(async {
+    ///         // do work...
+    ///     });
+    /// }
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    #[cfg(feature = "async-std")]
+    pub fn spawn_async_std<F>(&self, future: F) -> async_std::task::JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        async_std::task::spawn(self.track(future))
+    }
+
+    /// Like [`spawn`](Self::spawn), with `smol::spawn`.
+    ///
+    /// Dropping the returned task cancels it, and its worker is released once the executor
+    /// drops the future. Call `detach` on it to let it run in the background.
+    #[cfg(feature = "smol")]
+    pub fn spawn_smol<F>(&self, future: F) -> smol::Task<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        smol::spawn(self.track(future))
+    }
+
+    /// Runs `f` on a new thread, tracked by a worker of this group.
+    ///
+    /// The worker is released once `f` returns or panics, before the thread is joined.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    ///
+    /// let wg = WaitGroup::new();
+    /// let handle = wg.spawn_thread(|| {
+    ///     // do blocking work...
+    ///     42
+    /// });
+    /// wg.wait_blocking();
+    /// assert_eq!(handle.join().unwrap(), 42);
+    /// ```
+    pub fn spawn_thread<F, T>(&self, f: F) -> thread::JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        thread::spawn(self.wrap_fn(f))
+    }
+
+    /// Runs `f` on rayon's global thread pool, tracked by a worker of this group.
+    ///
+    /// The worker is released once `f` returns or panics.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// # async_std::task::block_on(async {
+    /// let wg = WaitGroup::new();
+    /// for chunk in 0..8 {
+    ///     wg.spawn_rayon(move || {
+    ///         // crunch `chunk`...
+    ///     });
+    /// }
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn spawn_rayon<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        rayon::spawn(self.wrap_fn(f));
+    }
+
+    /// Like [`spawn_rayon`](Self::spawn_rayon), on a custom rayon thread pool.
+    #[cfg(feature = "rayon")]
+    pub fn spawn_rayon_in<F>(&self, pool: &rayon::ThreadPool, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        pool.spawn(self.wrap_fn(f));
+    }
+
+    /// Runs `body` with a [`Scope`] to spawn tasks and resolves once the body and every task
+    /// it spawned have finished.
+    ///
+    /// The scope is a [`subgroup`](Self::subgroup), so it counts as one worker of this group
+    /// until it resolves.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// # async_std::task::block_on(async {
+    /// let wg = WaitGroup::new();
+    /// wg.scope(|s| async move {
+    ///     for _ in 0..10 {
+    ///         s.spawn(async {
+    ///             // do work...
+    ///         });
+    ///     }
+    /// })
+    /// .await;
+    /// // every spawned task has finished here
+    /// # });
+    /// ```
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn scope<F, Fut>(&self, body: F) -> ScopeFuture<Fut>
+    where
+        F: FnOnce(Scope) -> Fut,
+        Fut: Future,
+    {
+        ScopeFuture::new(body, self.subgroup())
+    }
+
+    /// Like [`scope`](Self::scope), but the spawned tasks may borrow from the caller, and the
+    /// current thread blocks until all of them have finished.
+    ///
+    /// This must not be called from an async task, as it blocks the executor thread. With
+    /// only the `tokio` feature, the thread must be in a runtime context, see
+    /// `tokio::runtime::Handle::enter`.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// let wg = WaitGroup::new();
+    /// let mut results = vec![0; 4];
+    /// wg.scope_blocking(|s| {
+    ///     for (i, result) in results.iter_mut().enumerate() {
+    ///         s.spawn(async move {
+    ///             *result = i * 2;
+    ///         });
+    ///     }
+    /// });
+    /// assert_eq!(results, [0, 2, 4, 6]);
+    /// ```
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn scope_blocking<'env, F, R>(&self, body: F) -> R
+    where
+        F: FnOnce(&BorrowedScope<'env>) -> R,
+    {
+        scope::scope_blocking(body, self.subgroup())
+    }
+
+    /// Like [`scope`](Self::scope), for fallible tasks.
+    ///
+    /// The first error or panic of the body or of a spawned task cancels the remaining tasks.
+    /// Once they are gone the error is returned, or the panic resumed, from the nursery.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// use async_std::task;
+    /// use std::time::Duration;
+    /// # task::block_on(async {
+    /// let wg = WaitGroup::new();
+    /// let result = wg
+    ///     .nursery(|n| async move {
+    ///         n.spawn(async {
+    ///             task::sleep(Duration::from_secs(60)).await;
+    ///             Ok(())
+    ///         });
+    ///         n.spawn(async { Err("connection refused") });
+    ///         Ok(())
+    ///     })
+    ///     .await;
+    /// assert_eq!(result, Err("connection refused"));
+    /// # });
+    /// ```
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn nursery<F, Fut, T, E>(&self, body: F) -> Nursery<Fut, T, E>
+    where
+        F: FnOnce(TaskScope<E>) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        Nursery::new(body, self.subgroup())
+    }
+
+    /// Drives `futures` concurrently in a `FuturesUnordered`, each holding a worker until it
+    /// completes.
+    ///
+    /// The returned stream yields the outputs in completion order. More futures can be added
+    /// with [`push`](TrackUnordered::push), and waiting on the group covers all of them.
+    ///
+    /// ```rust
+    /// use futures_util::StreamExt;
+    /// use waitgroup::WaitGroup;
+    /// # async_std::task::block_on(async {
+    /// let wg = WaitGroup::new();
+    /// let mut requests = wg.track_unordered((0..10).map(|i| async move { i * 2 }));
+    /// let mut sum = 0;
+    /// while let Some(n) = requests.next().await {
+    ///     sum += n;
+    /// }
+    /// assert_eq!(sum, 90);
+    /// assert!(wg.is_complete());
+    /// # });
+    /// ```
+    #[cfg(feature = "futures-util")]
+    pub fn track_unordered<I>(&self, futures: I) -> TrackUnordered<I::Item>
+    where
+        I: IntoIterator,
+        I::Item: Future,
+    {
+        TrackUnordered::new(futures, self.inner.clone())
+    }
+
+    /// Like [`track`](Self::track), but also returns a handle to join or abort this task.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// use async_std::task;
+    /// # task::block_on(async {
+    /// let wg = WaitGroup::new();
+    /// let (fut, handle) = wg.track_with_handle(async { 1 + 1 });
+    /// task::spawn(fut);
+    /// assert_eq!(handle.await, Ok(2));
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    pub fn track_with_handle<F: Future>(
+        &self,
+        future: F,
+    ) -> (TrackedTask<F, F::Output>, TrackedHandle<F::Output>) {
+        handle::pair(future, self.inner.acquire())
+    }
+
+    /// Like [`track`](Self::track), but also catches a panic of `future` and records it.
+    ///
+    /// The worker is released either way, the panic is counted by
+    /// [`panicked`](Self::panicked) and returned as the error.
+    pub fn track_catching<F: Future>(&self, future: F) -> CatchUnwind<F> {
+        CatchUnwind::new(future, self.inner.acquire())
+    }
+
+    /// Gets the number of workers that finished by panicking.
+    ///
+    /// This counts workers dropped while their thread unwinds, such as those held by a
+    /// [`wrap_fn`](Self::wrap_fn) closure, and panics caught by
+    /// [`track_catching`](Self::track_catching). Each panic is also reported as a
+    /// [`Panicked`] error to [`wait_result`](Self::wait_result).
+    pub fn panicked(&self) -> usize {
+        self.inner.panicked.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of workers that finished with [`Worker::complete_ok`].
+    pub fn succeeded(&self) -> usize {
+        self.inner.succeeded.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of workers that finished with [`Worker::complete_err`].
+    pub fn failed(&self) -> usize {
+        self.inner.failed.load(Ordering::Relaxed)
+    }
+
+    /// Raises the group's cancellation signal, returning `false` if it was already raised.
+    ///
+    /// Workers observe it through a [`CancelToken`], and keep counting until they finish.
+    pub fn cancel(&self) -> bool {
+        self.inner.cancel()
+    }
+
+    /// Returns `true` once the group's cancellation signal has been raised.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    /// Creates a child group that counts as a single worker of this one.
+    ///
+    /// The parent worker is released the first time the child drains, or when the child and
+    /// all its workers are dropped. Cancelling this group also cancels the child, see
+    /// [`link_child`](Self::link_child).
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// use async_std::task;
+    /// # task::block_on(async {
+    /// let wg = WaitGroup::new();
+    /// let stage = wg.subgroup();
+    /// for _ in 0..10 {
+    ///     let w = stage.worker();
+    ///     task::spawn(async move {
+    ///         // fan out further...
+    ///         drop(w);
+    ///     });
+    /// }
+    /// drop(stage);
+    ///
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    pub fn subgroup(&self) -> WaitGroup {
+        self.new_subgroup(None)
+    }
+
+    /// Like [`subgroup`](Self::subgroup), but runs `on_complete` when the child completes,
+    /// right before it releases its worker of this group.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// use async_std::task;
+    /// # task::block_on(async {
+    /// let wg = WaitGroup::new();
+    /// let stage = wg.subgroup_with(|| println!("fetch stage done"));
+    /// let w = stage.worker();
+    /// task::spawn(async move {
+    ///     // fetch...
+    ///     drop(w);
+    /// });
+    /// drop(stage);
+    ///
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    pub fn subgroup_with(&self, on_complete: impl FnOnce() + Send + 'static) -> WaitGroup {
+        self.new_subgroup(Some(Box::new(on_complete)))
+    }
+
+    fn new_subgroup(&self, on_complete: Option<Box<dyn FnOnce() + Send>>) -> WaitGroup {
+        let child = WaitGroup::new();
+        *child.inner.parent.lock().unwrap() = Some(Parent {
+            on_complete,
+            _worker: self.inner.acquire(),
+        });
+        self.link_child(&child);
+        child
+    }
+
+    /// Links `child` so that cancelling this group also cancels it.
+    ///
+    /// The child is cancelled right away if this group already is. Links are one-way and
+    /// do not keep the child alive.
+    pub fn link_child(&self, child: &WaitGroup) {
+        self.inner.link_child(&child.inner);
+    }
+
+    /// Gets a handle to the group's cancellation signal.
+    pub fn cancel_token(&self) -> CancelToken {
+        CancelToken(self.inner.clone())
+    }
+
+    /// Gets a tokio-util token that is cancelled together with the group.
+    ///
+    /// Cancelling the returned token does not cancel the group.
+    #[cfg(feature = "tokio-util")]
+    pub fn child_token(&self) -> tokio_util::sync::CancellationToken {
+        self.inner.token.child_token()
+    }
+
+    /// Enters a synchronous scope that the group waits for until the guard is dropped.
+    ///
+    /// This is the same as [`worker`](Self::worker), for blocking code and `Drop` impls.
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    ///
+    /// let wg = WaitGroup::new();
+    /// {
+    ///     let _guard = wg.enter();
+    ///     // do blocking work...
+    /// }
+    /// wg.wait_blocking();
+    /// ```
+    pub fn enter(&self) -> Guard {
+        Guard {
+            _worker: self.inner.acquire(),
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Creates a worker unless [`max_workers`](Builder::max_workers) are already active.
+    ///
+    /// Unlike [`acquire`](Self::acquire) this fails fast, so callers can shed load.
+    pub fn try_worker(&self) -> Option<Worker> {
+        self.inner.try_acquire()
+    }
+
+    /// Creates a worker labeled with `name`.
+    ///
+    /// Named workers are listed by [`pending`](Self::pending) until they finish, which helps
+    /// to find out what a slow shutdown is waiting on. Clones of the worker keep the name.
+    pub fn named_worker(&self, name: impl Into<Arc<str>>) -> Worker {
+        let mut worker = self.inner.acquire();
+        worker.name = Some(name.into());
+        self.inner.register(&mut worker);
+        worker
+    }
+
+    /// Creates a worker carrying `meta`, such as a request id.
+    ///
+    /// Like named workers, it is listed by [`pending`](Self::pending) until it finishes.
+    /// Clones of the worker share the value.
+    pub fn worker_with<T: Any + Send + Sync>(&self, meta: T) -> Worker {
+        let mut worker = self.inner.acquire();
+        worker.meta = Some(Arc::new(meta));
+        self.inner.register(&mut worker);
+        worker
+    }
+
+    /// Creates a worker belonging to the cohort `tag`, see [`wait_for_tag`](Self::wait_for_tag).
+    ///
+    /// Clones of the worker keep the tag.
+    pub fn tagged_worker(&self, tag: &'static str) -> Worker {
+        let mut worker = self.inner.acquire();
+        worker.tag = Some(tag);
+        self.inner.register(&mut worker);
+        worker
+    }
+
+    /// Waits for all workers tagged with `tag` to finish, the others keep running.
+    pub fn wait_for_tag(&self, tag: &'static str) -> WaitGroupFuture {
+        self.inner.wait_for(Until::Tag(tag))
+    }
+
+    /// Creates a worker that is expected to finish by `deadline`.
+    ///
+    /// A worker that is still active after its deadline counts as an overrun, see
+    /// [`Stats::overruns`]. With [`release_overdue`](Builder::release_overdue) it also stops
+    /// holding up the group at its deadline. Clones of the worker keep the deadline.
+    pub fn worker_with_deadline(&self, deadline: Instant) -> Worker {
+        let mut worker = self.inner.acquire();
+        worker.deadline = Some(deadline);
+        self.inner.register(&mut worker);
+        if self.inner.release_overdue {
+            // pending waits pick up the new deadline on their next poll
+            self.inner.waiters.wake_all();
+        }
+        worker
+    }
+
+    /// Gets the number of workers that were still active after their deadline.
+    pub fn overruns(&self) -> usize {
+        self.inner.overruns.load(Ordering::Relaxed)
+    }
+
+    /// Gets the named, tagged, and workers with metadata that are still active, oldest first.
+    pub fn pending(&self) -> Vec<WorkerInfo> {
+        self.inner.registry.lock().unwrap().snapshot()
+    }
+
+    /// Gets the workers that did not [`touch`](Worker::touch) within the last `window`, oldest
+    /// first.
+    ///
+    /// Only workers that show up in [`pending`](Self::pending) are considered, which
+    /// includes every worker that called `touch` at least once.
+    pub fn stale(&self, window: Duration) -> Vec<WorkerInfo> {
+        match Instant::now().checked_sub(window) {
+            Some(cutoff) => self.inner.registry.lock().unwrap().stale(cutoff),
+            None => Vec::new(),
+        }
+    }
+
+    /// Gets the metadata of type `T` of all active workers, oldest first.
+    pub fn pending_meta<T: Any + Clone>(&self) -> Vec<T> {
+        self.pending()
+            .iter()
+            .filter_map(|info| info.meta::<T>().cloned())
+            .collect()
+    }
+
+    /// Creates a worker that accounts for `n` units of work.
+    ///
+    /// The group completes when the weighted sum of its workers reaches zero, so waits like
+    /// [`wait_until_below`](Self::wait_until_below) and [`workers`](Self::workers) count
+    /// units. Clones of the worker have the same weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn worker_weighted(&self, n: usize) -> Worker {
+        assert!(n > 0, "a worker must have a weight of at least one");
+        self.inner.acquire_weighted(n)
+    }
+
+    /// Creates a worker that delivers a value when it completes, see
+    /// [`wait_values`](Self::wait_values).
+    pub fn worker_with_result<T: Any + Send>(&self) -> ResultWorker<T> {
+        ResultWorker::new(self.inner.acquire())
+    }
+
+    /// Creates `n` workers at once, reserving them in a single atomic operation.
+    pub fn workers_batch(&self, n: usize) -> Vec<Worker> {
+        self.inner.acquire_batch(n)
+    }
+
+    /// Adds `n` to the count of active workers without creating [`Worker`] handles.
+    ///
+    /// Each unit must be released by a call to [`done`](Self::done), as with Go's `WaitGroup`.
+    /// This is useful when completion is signaled from callback-based code.
+    ///
+    /// The units are waited for by [`wait_checkpoint`](Self::wait_checkpoint) and released
+    /// by [`Escalation::Detach`] like workers. They are interchangeable, so `done` finishes
+    /// the oldest one.
+    pub fn add(&self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        let epoch = self.inner.join(self.inner.epoch.lock().unwrap().clone(), n);
+        self.inner.add(n);
+        let mut loose = self.inner.loose.lock().unwrap();
+        match loose.back_mut() {
+            Some((last, units)) if Arc::ptr_eq(last, &epoch) => *units += n,
+            _ => loose.push_back((epoch, n)),
+        }
+    }
+
+    /// Marks one unit added by [`add`](Self::add) as finished.
+    ///
+    /// Finishing a unit released by [`Escalation::Detach`] has no effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no units added by `add` left.
+    pub fn done(&self) {
+        let mut loose = self.inner.loose.lock().unwrap();
+        let (epoch, units) = loose.front_mut().expect("negative WaitGroup counter");
+        *units -= 1;
+        let epoch = if *units == 0 {
+            loose.pop_front().unwrap().0
+        } else {
+            epoch.clone()
+        };
+        drop(loose);
+        if epoch.round.leave(1) {
+            self.inner.release(1, true);
+        }
+        // a checkpoint may be waiting for the epoch
+        drop(epoch);
+    }
+
+    /// Gets the number of active workers.
+    pub fn workers(&self) -> usize {
+        self.inner.workers()
+    }
+
+    /// Gets the maximum number of workers that were active at the same time over the
+    /// lifetime of the group.
+    ///
+    /// Unlike sampling [`workers`](Self::workers), this catches every spike, which makes it a
+    /// good measure for sizing pools.
+    pub fn peak_workers(&self) -> usize {
+        self.inner.peak.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of workers created over the lifetime of the group, including those
+    /// that have finished.
+    ///
+    /// Together with [`workers`](Self::workers) this tracks the progress of a batch job.
+    pub fn total_workers(&self) -> usize {
+        self.inner.total.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if all workers have finished, without waiting.
+    pub fn is_complete(&self) -> bool {
+        self.workers() == 0
+    }
+
+    /// Waits for all workers to finish.
+    ///
+    /// The group stays usable, so more workers can be added and it can be waited on again
+    /// for the next round. A round that drains completely is never missed, even if new
+    /// workers are added before the future is polled.
+    pub fn wait(&self) -> WaitGroupFuture {
+        self.inner.wait()
+    }
+
+    /// Waits for the workers that are active right now to finish.
+    ///
+    /// Workers created after this call, including clones of existing workers, are not
+    /// waited for.
+    pub fn wait_checkpoint(&self) -> WaitGroupFuture {
+        let epoch = self.inner.next_epoch(None);
+        self.inner
+            .wait_for(Until::Checkpoint(Arc::downgrade(&epoch)))
+    }
+
+    /// Waits until fewer than `n` workers are active.
+    ///
+    /// The returned future resolves once, call this again to wait for the next time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn wait_until_below(&self, n: usize) -> WaitGroupFuture {
+        assert!(n > 0, "the number of workers can never drop below zero");
+        self.inner.wait_for(Until::Below(n))
+    }
+
+    /// Waits until any worker finishes.
+    ///
+    /// Resolves immediately if a worker has already finished.
+    pub fn wait_any(&self) -> WaitGroupFuture {
+        self.wait_quorum(1)
+    }
+
+    /// Waits until at least `n` workers have finished, the rest keep running.
+    ///
+    /// Finished workers are counted over the lifetime of the group.
+    pub fn wait_quorum(&self, n: usize) -> WaitGroupFuture {
+        self.inner.wait_for(Until::Completed(n))
+    }
+
+    /// Folds the outstanding workers of `other` into this group.
+    ///
+    /// They count as a single worker of this group until all of them have finished.
+    pub fn merge(&self, other: WaitGroup) {
+        let worker = self.worker();
+        other.inner.on_drain(move || drop(worker));
+    }
+
+    /// Splits the group into a [`Spawner`] that creates workers and a [`Waiter`] that waits for them.
+    pub fn split(self) -> (Spawner, Waiter) {
+        (Spawner(self.inner.clone()), Waiter(self.inner.clone()))
+    }
+
+    /// Polls for all workers to finish, without creating a [`WaitGroupFuture`].
+    ///
+    /// This is meant for hand-written poll loops. Only the most recent `cx` is woken. Like
+    /// [`wait`](Self::wait), it resolves once the round of workers that was active on the
+    /// first poll drains, even if new workers were added by the time it is polled again.
+    pub fn poll_wait(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let inner = &self.inner;
+        let until = self.polled.get_or_insert_with(|| inner.drained());
+        let poll = inner.poll_for(until, &mut self.key, cx);
+        if poll.is_ready() {
+            self.polled = None;
+        }
+        poll
+    }
+
+    /// Blocks the current thread until all workers finish.
+    ///
+    /// This does not need an executor, so it can be called from plain threads.
+    pub fn wait_blocking(&self) {
+        let mut wait = self.wait();
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        while Pin::new(&mut wait).poll(&mut cx).is_pending() {
+            thread::park();
+        }
+    }
+
+    /// Waits for all workers to finish, giving up after `dur`.
+    pub fn wait_timeout(&self, dur: Duration) -> Timeout {
+        self.wait().timeout(dur)
+    }
+
+    /// Waits for all workers to finish, giving up at `deadline`.
+    pub fn wait_until(&self, deadline: Instant) -> Timeout {
+        self.wait().timeout_at(deadline)
+    }
+
+    /// Waits up to `timeout` for all workers to finish, and reports the stragglers if they
+    /// did not.
+    ///
+    /// Named workers and workers with metadata are listed by name and metadata, which is
+    /// what a shutdown log needs.
+    pub fn drain(&self, timeout: Duration) -> Drain {
+        Drain::new(&self.inner, Some(timeout))
+    }
+
+    /// Waits for all workers to finish, or for `cancel` to resolve, whichever comes first.
+    ///
+    /// If both are ready at the same time, completion wins.
+    pub fn wait_or<C: Future>(&self, cancel: C) -> WaitOr<C> {
+        WaitOr {
+            wait: self.wait(),
+            cancel,
+        }
+    }
+
+    /// Waits for all workers to finish and reports statistics about the run.
+    pub fn wait_with_stats(&self) -> WaitWithStats {
+        WaitWithStats { wait: self.wait() }
+    }
+
+    /// Waits for all workers to finish and resolves to the first error reported by
+    /// [`Worker::fail`], if any.
+    ///
+    /// The error is taken out of the group, so it is only returned once.
+    pub fn wait_result(&self) -> WaitResult {
+        WaitResult { wait: self.wait() }
+    }
+
+    /// Waits for all workers to finish and resolves to the values of type `T` delivered by
+    /// [`ResultWorker::complete`], in the order they were delivered.
+    ///
+    /// The values are taken out of the group, so each is only returned once.
+    pub fn wait_values<T: Any + Send>(&self) -> WaitValues<T> {
+        WaitValues::new(self.wait())
+    }
+
+    /// Like [`wait_result`](Self::wait_result), but resolves to an [`anyhow::Result`].
+    #[cfg(feature = "anyhow")]
+    pub fn wait_anyhow(&self) -> WaitAnyhow {
+        WaitAnyhow {
+            wait: self.wait_result(),
+        }
+    }
+
+    /// Waits for all workers to finish and resolves to the errors reported by
+    /// [`Worker::fail`], if any.
+    ///
+    /// This only includes every error with [`collect_errors`](Builder::collect_errors). The
+    /// errors are taken out of the group, so each is only returned once.
+    pub fn wait_errors(&self) -> WaitErrors {
+        WaitErrors { wait: self.wait() }
+    }
+}
+
+impl CancelToken {
+    /// Raises the signal, returning `false` if it was already raised.
+    pub fn cancel(&self) -> bool {
+        self.0.cancel()
+    }
+
+    /// Returns `true` once the signal has been raised.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.is_cancelled()
+    }
+
+    /// Waits for the signal to be raised.
+    pub fn cancelled(&self) -> WaitGroupFuture {
+        self.0.wait_for(Until::Cancelled)
+    }
+}
+
+impl Spawner {
+    pub fn worker(&self) -> Worker {
+        self.0.acquire()
+    }
+
+    /// Gets the number of active workers.
+    pub fn workers(&self) -> usize {
+        self.0.workers()
+    }
+}
+
+impl Waiter {
+    /// Gets the number of active workers.
+    pub fn workers(&self) -> usize {
+        self.0.workers()
+    }
+
+    /// Returns `true` if all workers have finished, without waiting.
+    pub fn is_complete(&self) -> bool {
+        self.workers() == 0
+    }
+
+    pub fn wait(&self) -> WaitGroupFuture {
+        self.0.wait()
+    }
+}
+
+impl IntoFuture for Waiter {
+    type Output = ();
+    type IntoFuture = WaitGroupFuture;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.wait()
+    }
+}
+
+impl Worker {
+    /// Marks the worker as finished.
+    ///
+    /// This is the same as dropping it, but reads explicitly at the call site. Returns `true`
+    /// if this was the last active worker, which is decided atomically, so exactly one of
+    /// several workers finishing concurrently sees `true`.
+    pub fn done(mut self) -> bool {
+        self.finish(true) == Some(0)
+    }
+
+    /// Marks the worker as finished successfully, see [`WaitGroup::succeeded`].
+    ///
+    /// Returns `true` if this was the last active worker, like [`done`](Self::done).
+    pub fn complete_ok(self) -> bool {
+        self.inner.succeeded.fetch_add(1, Ordering::Relaxed);
+        self.done()
+    }
+
+    /// Marks the worker as failed, see [`WaitGroup::failed`].
+    ///
+    /// It still counts as finished for waiting. Returns `true` if this was the last active
+    /// worker, like [`done`](Self::done).
+    pub fn complete_err(self) -> bool {
+        self.inner.failed.fetch_add(1, Ordering::Relaxed);
+        self.done()
+    }
+
+    /// Records that the worker is still making progress, see [`WaitGroup::stale`].
+    ///
+    /// The first call adds the worker to [`WaitGroup::pending`] if it was not listed yet.
+    pub fn touch(&mut self) {
+        if !self.registered {
+            self.inner.clone().register(self);
+        }
+        let now = Instant::now();
+        self.inner.registry.lock().unwrap().touch(self.id, now);
+    }
+
+    /// Marks the worker as failed with `error`, see [`WaitGroup::wait_result`].
+    ///
+    /// Only the first error of the group is kept, unless it
+    /// [collects errors](Builder::collect_errors). This is the same as
+    /// [`complete_err`](Self::complete_err) otherwise.
+    pub fn fail(self, error: impl Into<BoxError>) -> bool {
+        self.inner.report(error.into());
+        self.complete_err()
+    }
+
+    /// Returns `true` if this is the only active worker in the group, that is if its
+    /// [`weight`](Self::weight) accounts for all [`remaining`](Self::remaining) units.
+    ///
+    /// This is a snapshot: the group or other handles may create new workers afterwards. Use
+    /// the result of [`done`](Self::done) to run a finalization step exactly once.
+    pub fn is_last(&self) -> bool {
+        self.remaining() == self.weight
+    }
+
+    /// Removes the worker from the group without waiting for its task to finish.
+    ///
+    /// Unlike [`done`](Self::done), the worker does not count as finished for
+    /// [`WaitGroup::wait_any`] and [`WaitGroup::wait_quorum`].
+    pub fn detach(mut self) {
+        self.finish(false);
+    }
+
+    /// Replaces the worker with a new one, without the count ever dropping.
+    ///
+    /// The replacement takes over the unit of work, so it is still waited for by checkpoints
+    /// taken before the handoff. This suits supervisors that restart a task.
+    pub fn handoff(mut self) -> Worker {
+        self.released = true;
+        // the replacement takes over the id and the registry entry as well, so observers see
+        // the same worker
+        let mut worker = self.inner.handle_as(self.epoch.clone(), self.id);
+        worker.name = self.name.take();
+        worker.tag = self.tag.take();
+        worker.meta = self.meta.take();
+        worker.created = self.created;
+        worker.deadline = self.deadline;
+        worker.weight = self.weight;
+        worker.registered = mem::take(&mut self.registered);
+        worker
+    }
+
+    /// Splits the worker into `n` workers that each finish independently.
+    ///
+    /// Like [`handoff`](Self::handoff), the count never drops while splitting, and the
+    /// children are waited for by checkpoints taken before. Splitting into zero workers is
+    /// the same as [`done`](Self::done).
+    pub fn split(mut self, n: usize) -> Vec<Worker> {
+        if n == 0 {
+            self.done();
+            return Vec::new();
+        }
+        // the children take over this worker's units, unless they were already released
+        self.released = true;
+        self.report_finished(false, false);
+        let registry = self.inner.registry.lock().unwrap();
+        let counted = !registry.is_expired(self.id) && self.epoch.round.leave(self.weight);
+        drop(registry);
+        let kept = if counted { self.weight } else { 0 };
+        self.inner.add(n * self.weight - kept);
+        (0..n)
+            .map(|_| self.inherit(self.inner.handle(self.epoch.clone(), self.weight)))
+            .collect()
+    }
+
+    /// Gives `worker` the same weight, label, tag, and metadata as this one.
+    fn inherit(&self, mut worker: Worker) -> Worker {
+        worker.weight = self.weight;
+        worker.name = self.name.clone();
+        worker.tag = self.tag;
+        worker.meta = self.meta.clone();
+        worker.deadline = self.deadline;
+        if self.registered {
+            self.inner.register(&mut worker);
+        }
+        worker
+    }
+
+    /// Reports to the log, traces, and observer that the worker finished.
+    fn report_finished(&mut self, completed: bool, expired: bool) {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(parent: &self.span, completed, expired, "worker finished");
+        #[cfg(feature = "otel")]
+        otel::finish(&mut self.otel_span, self.name.as_deref(), completed);
+        if let Some(observer) = &self.inner.observer {
+            observer.0.on_worker_dropped(self.id, completed);
+        }
+        #[cfg(feature = "log")]
+        log::trace!(
+            "{}: worker {} finished, completed: {}",
+            self.inner.label(),
+            self.id,
+            completed
+        );
+        #[cfg(not(feature = "tracing"))]
+        let _ = expired;
+    }
+
+    /// Removes the worker from the registry and releases its units, unless that already
+    /// happened. Returns the new count if the units were released here.
+    fn finish(&mut self, completed: bool) -> Option<usize> {
+        let released = mem::replace(&mut self.released, true);
+        let mut expired = false;
+        if mem::take(&mut self.registered) {
+            let mut registry = self.inner.registry.lock().unwrap();
+            if let Some(info) = registry.remove(self.id) {
+                let drained = info.tag.filter(|tag| registry.tagged(tag) == 0);
+                drop(registry);
+                expired = info.expired;
+                let overdue = info.deadline.map_or(false, |d| Instant::now() > d);
+                if overdue && !released && !expired {
+                    self.inner.overruns.fetch_add(1, Ordering::Relaxed);
+                }
+                if let Some(tag) = drained {
+                    self.inner
+                        .waiters
+                        .wake(|until| matches!(until, Until::Tag(t) if *t == tag));
+                }
+            }
+        }
+        if released {
+            return None;
+        }
+        self.report_finished(completed, expired);
+        // before releasing, so the message is queued by the time a waiter resumes
+        #[cfg(feature = "async-channel")]
+        if let Some(sender) = &self.inner.completions {
+            let units = if expired { 0 } else { self.weight };
+            let _ = sender.try_send(Completion {
+                id: self.id,
+                name: self.name.clone(),
+                tag: self.tag,
+                completed,
+                remaining: self.inner.workers().saturating_sub(units),
+            });
+        }
+        if expired || !self.epoch.round.leave(self.weight) {
+            return None;
+        }
+        Some(self.inner.release(self.weight, completed))
+    }
+
+    /// Returns `true` once the group's cancellation signal has been raised.
+    ///
+    /// This is a single atomic load, cheap enough to check in a hot loop to bail out early
+    /// during a drain.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    /// Waits for the group's cancellation signal, see [`WaitGroup::cancel`].
+    ///
+    /// ```rust
+    /// use waitgroup::WaitGroup;
+    /// use async_std::task;
+    /// # task::block_on(async {
+    /// let wg = WaitGroup::new();
+    /// let w = wg.worker();
+    /// task::spawn(async move {
+    ///     let work = futures_timer::Delay::new(std::time::Duration::from_secs(60));
+    ///     futures_util::future::select(Box::pin(work), w.cancelled()).await;
+    ///     drop(w);
+    /// });
+    ///
+    /// wg.cancel();
+    /// wg.wait().await;
+    /// # });
+    /// ```
+    pub fn cancelled(&self) -> WaitGroupFuture {
+        self.inner.wait_for(Until::Cancelled)
+    }
+
+    /// Gets a handle to the group's cancellation signal, see [`WaitGroup::cancel`].
+    pub fn cancel_token(&self) -> CancelToken {
+        CancelToken(self.inner.clone())
+    }
+
+    /// Gets the label of the worker, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Gets the tag of the worker, if it has one.
+    pub fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
+
+    /// Gets the span of the worker, a child of the group's span.
+    ///
+    /// Instrument the work with it to attribute its events to the worker.
+    #[cfg(feature = "tracing")]
+    pub fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+
+    /// Gets the metadata of the worker, if it has some of type `T`.
+    pub fn meta<T: Any>(&self) -> Option<&T> {
+        self.meta.as_ref()?.downcast_ref()
+    }
+
+    /// Gets an id that is unique among the workers of the group.
+    ///
+    /// Every clone gets a new id, a [`handoff`](Self::handoff) replacement keeps it.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Gets the number of units of work the worker accounts for.
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    /// Gets the number of active units of work in the group, including this worker's.
+    ///
+    /// Like [`WaitGroup::workers`], this counts each worker by its [`weight`](Self::weight).
+    pub fn remaining(&self) -> usize {
+        self.inner.workers()
+    }
+
+    /// Creates a handle to the group that does not count as a worker.
+    pub fn downgrade(&self) -> WeakWorker {
+        WeakWorker(Arc::downgrade(&self.inner))
+    }
+}
+
+impl WeakWorker {
+    /// Creates a new worker for the group.
+    ///
+    /// Returns `None` if the group and every handle to it have been dropped.
+    pub fn upgrade(&self) -> Option<Worker> {
+        self.0.upgrade().map(|inner| inner.acquire())
+    }
+}
+
+/// Workers are equal if they are the same handle, or a [`handoff`](Worker::handoff) of it.
+impl PartialEq for Worker {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner) && self.id == other.id
+    }
+}
+
+impl Eq for Worker {}
+
+impl Hash for Worker {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.inner).hash(state);
+        self.id.hash(state);
+    }
+}
+
+impl Clone for Worker {
+    fn clone(&self) -> Self {
+        self.inherit(self.inner.acquire_weighted(self.weight))
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        if thread::panicking() && !self.released {
+            self.inner.record_panic(None);
+        }
+        self.finish(true);
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+impl Drop for WaitGroup {
+    fn drop(&mut self) {
+        self.inner.waiters.remove(&mut self.key);
+    }
+}
+
+impl Default for WaitGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IntoFuture for WaitGroup {
+    type Output = ();
+    type IntoFuture = WaitGroupFuture;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.wait()
+    }
+}
+
+impl Future for WaitGroupFuture {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if this.inner.release_overdue {
+            this.poll_overdue(cx);
+        }
+        #[cfg(feature = "tokio-util")]
+        if matches!(this.until, Until::Cancelled) {
+            let inner = &this.inner;
+            let token = this
+                .token
+                .get_or_insert_with(|| Box::pin(inner.token.clone().cancelled_owned()));
+            // only registers the waker, `poll_for` reads the token
+            let _ = token.as_mut().poll(cx);
+        }
+        if this
+            .inner
+            .poll_for(&this.until, &mut this.key, cx)
+            .is_pending()
+        {
+            return Poll::Pending;
+        }
+        if this.never_started() {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(parent: &this.inner.span, "{}", NoWorkers);
+            #[cfg(feature = "log")]
+            log::warn!("{}: {}", this.inner.label(), NoWorkers);
+        }
+        this.done = true;
+        Poll::Ready(())
+    }
+}
+
+impl Clone for WaitGroupFuture {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            until: self.until.clone(),
+            key: None,
+            delay: None,
+            #[cfg(feature = "tokio-util")]
+            token: None,
+            done: self.done,
+        }
+    }
+}
+
+impl Drop for WaitGroupFuture {
+    fn drop(&mut self) {
+        self.inner.waiters.remove(&mut self.key);
+    }
+}
+
+impl FusedFuture for WaitGroupFuture {
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
+
+impl Future for Acquire {
+    type Output = Worker;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let until = Until::Below(this.inner.max_workers);
+        loop {
+            if let Some(worker) = this.inner.try_acquire() {
+                return Poll::Ready(worker);
+            }
+            // another task may take the free slot first, so try again once there is one
+            if this.inner.poll_for(&until, &mut this.key, cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        self.inner.waiters.remove(&mut self.key);
+    }
+}
+
+impl Future for Timeout {
+    type Output = Result<(), Elapsed>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(()) = Pin::new(&mut self.wait).poll(cx) {
+            return Poll::Ready(Ok(()));
+        }
+        match Pin::new(&mut self.delay).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(Elapsed {
+                workers: self.wait.workers(),
+            })),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<C: Future> Future for WaitOr<C> {
+    type Output = WaitOutcome;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if let Poll::Ready(()) = Pin::new(&mut *this.wait).poll(cx) {
+            return Poll::Ready(WaitOutcome::Completed);
+        }
+        match this.cancel.poll(cx) {
+            Poll::Ready(_) => Poll::Ready(WaitOutcome::Cancelled {
+                remaining: this.wait.workers(),
+            }),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Future for WaitWithStats {
+    type Output = Stats;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.wait).poll(cx) {
+            Poll::Ready(()) => {
+                let inner = &self.wait.inner;
+                Poll::Ready(Stats {
+                    elapsed: inner.created.elapsed(),
+                    peak_workers: inner.peak.load(Ordering::Relaxed),
+                    total_workers: inner.total.load(Ordering::Relaxed),
+                    overruns: inner.overruns.load(Ordering::Relaxed),
+                    panicked: inner.panicked.load(Ordering::Relaxed),
+                })
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Future for WaitResult {
+    type Output = Result<(), BoxError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.wait).poll(cx) {
+            Poll::Ready(()) => {
+                if self.wait.never_started() {
+                    return Poll::Ready(Err(Box::new(NoWorkers)));
+                }
+                let errors = mem::take(&mut *self.wait.inner.errors.lock().unwrap());
+                match errors.into_iter().next() {
+                    Some(error) => Poll::Ready(Err(error)),
+                    None => Poll::Ready(Ok(())),
+                }
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl Future for WaitAnyhow {
+    type Output = anyhow::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.wait).poll(cx) {
+            Poll::Ready(result) => Poll::Ready(result.map_err(anyhow::Error::from_boxed)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Future for WaitErrors {
+    type Output = Result<(), Errors>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.wait).poll(cx) {
+            Poll::Ready(()) => {
+                let mut errors = mem::take(&mut *self.wait.inner.errors.lock().unwrap());
+                if self.wait.never_started() {
+                    errors.insert(0, Box::new(NoWorkers));
+                }
+                if errors.is_empty() {
+                    Poll::Ready(Ok(()))
+                } else {
+                    Poll::Ready(Err(Errors { errors }))
+                }
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::task;
+
+    #[async_std::test]
+    async fn smoke() {
+        let wg = WaitGroup::new();
+
+        for _ in 0..100 {
+            let w = wg.worker();
+            task::spawn(async move {
+                drop(w);
+            });
+        }
+
+        wg.wait().await;
+    }
+
+    #[async_std::test]
+    async fn workers_batch() {
+        let wg = WaitGroup::new();
+        let workers = wg.workers_batch(100);
+        assert_eq!(wg.workers(), 100);
+        for w in workers {
+            task::spawn(async move {
+                drop(w);
+            });
+        }
+
+        wg.wait().await;
+    }
+
+    #[async_std::test]
+    async fn into_future() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        task::spawn(async move {
+            drop(w);
+        });
+
+        wg.await;
+    }
+
+    #[async_std::test]
+    async fn fused() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        let mut wait = wg.wait();
+        assert!(!wait.is_terminated());
+        drop(w);
+        (&mut wait).await;
+        assert!(wait.is_terminated());
+    }
+
+    #[async_std::test]
+    async fn multiple_waiters() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        let wait = wg.wait();
+        let waiters: Vec<_> = (0..10).map(|_| task::spawn(wait.clone())).collect();
+        task::sleep(Duration::from_millis(10)).await;
+        drop(w);
+        for waiter in waiters {
+            waiter.await;
+        }
+    }
+
+    #[async_std::test]
+    async fn wait_or() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        let outcome = wg.wait_or(task::sleep(Duration::from_millis(10))).await;
+        assert_eq!(outcome, WaitOutcome::Cancelled { remaining: 1 });
+
+        drop(w);
+        let outcome = wg.wait_or(std::future::pending::<()>()).await;
+        assert_eq!(outcome, WaitOutcome::Completed);
+    }
+
+    #[async_std::test]
+    async fn stats() {
+        let wg = WaitGroup::new();
+        let workers: Vec<_> = (0..10).map(|_| wg.worker()).collect();
+        drop(workers);
+        let w = wg.worker();
+        task::spawn(async move {
+            drop(w.clone());
+            drop(w);
+        });
+
+        let stats = wg.wait_with_stats().await;
+        assert_eq!(stats.peak_workers(), 10);
+        assert_eq!(stats.total_workers(), 12);
+    }
+
+    #[test]
+    fn peak_workers() {
+        let wg = WaitGroup::new();
+        assert_eq!(wg.peak_workers(), 0);
+        let workers = wg.workers_batch(3);
+        drop(workers);
+        let _w = wg.worker();
+        assert_eq!(wg.peak_workers(), 3);
+    }
+
+    #[test]
+    fn total_workers() {
+        let wg = WaitGroup::new();
+        let workers = wg.workers_batch(3);
+        drop(workers);
+        let w = wg.worker();
+        drop(w.clone());
+        assert_eq!(wg.total_workers(), 5);
+        assert_eq!(wg.workers(), 1);
+    }
+
+    #[async_std::test]
+    async fn wait_until_below() {
+        let wg = WaitGroup::new();
+        let mut workers: Vec<_> = (0..10).map(|_| wg.worker()).collect();
+        let wait = wg.wait_until_below(5);
+        let waiter = task::spawn(wait.clone());
+        while workers.len() > 4 {
+            task::sleep(Duration::from_millis(1)).await;
+            workers.pop();
+        }
+        waiter.await;
+        assert_eq!(wait.workers(), 4);
+
+        // can be waited on again as the count rises and falls
+        workers.extend((0..6).map(|_| wg.worker()));
+        let waiter = task::spawn(wg.wait_until_below(5));
+        workers.truncate(3);
+        waiter.await;
+    }
+
+    #[async_std::test]
+    async fn wait_any() {
+        let wg = WaitGroup::new();
+        let fast = wg.worker();
+        let slow = wg.worker();
+        task::spawn(async move {
+            task::sleep(Duration::from_millis(10)).await;
+            drop(fast);
+        });
+
+        wg.wait_any().await;
+        assert_eq!(wg.workers(), 1);
+        drop(slow);
+    }
+
+    #[async_std::test]
+    async fn wait_quorum() {
+        let wg = WaitGroup::new();
+        let mut replicas: Vec<_> = (0..5).map(|_| wg.worker()).collect();
+        let waiter = task::spawn(wg.wait_quorum(3));
+        for _ in 0..3 {
+            task::sleep(Duration::from_millis(1)).await;
+            replicas.pop();
+        }
+
+        waiter.await;
+        assert_eq!(wg.workers(), 2);
+    }
+
+    #[async_std::test]
+    async fn wait_checkpoint() {
+        let wg = WaitGroup::new();
+        let old = wg.worker();
+        let wait = wg.wait_checkpoint();
+        let new = wg.worker();
+        let sub = old.clone();
+        assert!(!wait.until.is_reached(&wait.inner));
+
+        drop(old);
+        wait.clone().await;
+        assert_eq!(wg.workers(), 2);
+
+        let wait = wg.wait_checkpoint();
+        drop(new);
+        assert!(!wait.until.is_reached(&wait.inner));
+        drop(sub);
+        wait.await;
+    }
+
+    #[async_std::test]
+    async fn add_done() {
+        let wg = Arc::new(WaitGroup::new());
+        wg.add(10);
+        let w = wg.worker();
+        for _ in 0..10 {
+            let wg = wg.clone();
+            std::thread::spawn(move || wg.done());
+        }
+        drop(w);
+
+        wg.wait().await;
+    }
+
+    #[async_std::test]
+    async fn add_checkpoint() {
+        let wg = WaitGroup::new();
+        wg.add(2);
+        let wait = wg.wait_checkpoint();
+        wg.add(1);
+        wg.done();
+        assert!(!wait.until.is_reached(&wait.inner));
+        wg.done();
+        wait.await;
+        assert_eq!(wg.workers(), 1);
+        wg.done();
+    }
+
+    #[test]
+    #[should_panic(expected = "negative WaitGroup counter")]
+    fn done_without_add() {
+        WaitGroup::new().done();
+    }
+
+    #[async_std::test]
+    async fn merge() {
+        let wg = WaitGroup::new();
+        let other = WaitGroup::new();
+        let workers: Vec<_> = (0..3).map(|_| other.worker()).collect();
+        wg.merge(other);
+        wg.merge(WaitGroup::new());
+        assert_eq!(wg.workers(), 1);
+
+        task::spawn(async move {
+            drop(workers);
+        });
+        wg.wait().await;
+    }
+
+    #[async_std::test]
+    async fn strict() {
+        let wg = WaitGroup::builder().strict(true).build();
+        wg.wait().await;
+        wg.wait_blocking();
+        let error = wg.wait_result().await.unwrap_err();
+        assert!(error.is::<NoWorkers>());
+        assert_eq!(wg.wait_errors().await.unwrap_err().len(), 1);
+
+        drop(wg.worker());
+        assert!(wg.wait_result().await.is_ok());
+        assert!(WaitGroup::new().wait_result().await.is_ok());
+    }
+
+    #[async_std::test]
+    async fn split() {
+        let (spawner, waiter) = WaitGroup::new().split();
+
+        for _ in 0..100 {
+            let w = spawner.clone().worker();
+            task::spawn(async move {
+                drop(w);
+            });
+        }
+        drop(spawner);
+
+        waiter.await;
+    }
+
+    #[async_std::test]
+    async fn reuse() {
+        let wg = WaitGroup::new();
+
+        for _ in 0..2 {
+            for _ in 0..100 {
+                let w = wg.worker();
+                task::spawn(async move {
+                    drop(w);
+                });
+            }
+
+            wg.wait().await;
+            assert!(wg.is_complete());
+        }
+    }
+
+    #[async_std::test]
+    async fn next_generation() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        let wait = wg.wait();
+        drop(w);
+        // the next round starts before the waiter gets to observe the drained group
+        let _w = wg.worker();
+
+        wait.await;
+    }
+
+    #[test]
+    fn worker_done() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        w.clone().done();
+        assert_eq!(wg.workers(), 1);
+        w.done();
+        assert!(wg.is_complete());
+    }
+
+    #[test]
+    fn is_last() {
+        let wg = WaitGroup::new();
+        let workers: Vec<_> = (0..100).map(|_| wg.worker()).collect();
+        assert!(!workers[0].is_last());
+        let threads: Vec<_> = workers
+            .into_iter()
+            .map(|w| std::thread::spawn(move || w.done()))
+            .collect();
+        let last: usize = threads
+            .into_iter()
+            .map(|t| t.join().unwrap() as usize)
+            .sum();
+        assert_eq!(last, 1);
+
+        let w = wg.worker();
+        assert!(w.is_last());
+        drop(w);
+
+        let heavy = wg.worker_weighted(5);
+        assert!(heavy.is_last());
+        let light = wg.worker();
+        assert_eq!(heavy.remaining(), 6);
+        assert!(!heavy.is_last());
+        drop(light);
+        assert!(heavy.is_last());
+    }
+
+    #[async_std::test]
+    async fn handoff() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        let checkpoint = wg.wait_checkpoint();
+        let any = wg.wait_any();
+
+        let w = w.handoff();
+        assert_eq!(wg.workers(), 1);
+        assert_eq!(wg.inner.total.load(Ordering::Relaxed), 1);
+        assert!(!checkpoint.until.is_reached(&checkpoint.inner));
+        assert!(!any.until.is_reached(&any.inner));
+
+        w.done();
+        checkpoint.await;
+    }
+
+    #[async_std::test]
+    async fn split_worker() {
+        let wg = WaitGroup::new();
+        let shards = wg.worker().split(4);
+        assert_eq!(wg.workers(), 4);
+        for shard in shards {
+            task::spawn(async move {
+                drop(shard);
+            });
+        }
+        wg.wait().await;
+
+        assert!(wg.worker().split(0).is_empty());
+        assert!(wg.is_complete());
+    }
+
+    #[test]
+    fn named_worker() {
+        let wg = WaitGroup::new();
+        let flush = wg.named_worker("flush-db");
+        let _plain = wg.worker();
+        let sub = flush.clone();
+        assert_eq!(sub.name(), Some("flush-db"));
+
+        let pending = wg.pending();
+        assert_eq!(pending.len(), 2);
+        assert!(pending.iter().all(|info| info.name() == Some("flush-db")));
+
+        drop(flush);
+        let sub = sub.handoff();
+        assert_eq!(wg.pending().len(), 1);
+        drop(sub);
+        assert!(wg.pending().is_empty());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing() {
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        impl Visit for Recorder {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    self.0.lock().unwrap().push(format!("{:?}", value));
+                }
+            }
+        }
+
+        impl tracing::Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut spans = self.0.lock().unwrap();
+                spans.push(span.metadata().name().to_string());
+                Id::from_u64(spans.len() as u64)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                event.record(&mut self.clone());
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let wg = WaitGroup::builder().name("jobs").build();
+            let worker = wg.named_worker("job");
+            assert!(!worker.span().is_disabled());
+            drop(worker);
+        });
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [
+                "waitgroup",
+                "worker",
+                "worker created",
+                "worker finished",
+                "group drained"
+            ]
+        );
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn log() {
+        struct Logger;
+
+        static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        impl log::Log for Logger {
+            fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record<'_>) {
+                let message = record.args().to_string();
+                if message.starts_with("logged: ") {
+                    MESSAGES.lock().unwrap().push(message);
+                }
+            }
+            fn flush(&self) {}
+        }
+
+        log::set_logger(&Logger).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        let wg = WaitGroup::builder().name("logged").build();
+        drop(wg.worker());
+        assert_eq!(
+            *MESSAGES.lock().unwrap(),
+            [
+                "logged: worker 0 created",
+                "logged: worker 0 finished, completed: true",
+                "logged: drained"
+            ]
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let wg = WaitGroup::builder().name("metered").build();
+        let _workers = metrics::with_local_recorder(&recorder, || {
+            drop(wg.worker());
+            drop(WaitGroup::new().worker());
+            wg.workers_batch(3)
+        });
+        let mut values: Vec<_> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let key = key.key();
+                let labels: Vec<_> = key.labels().map(|l| l.value().to_string()).collect();
+                (key.name().to_string(), labels, value)
+            })
+            .collect();
+        values.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            values,
+            [
+                (
+                    "waitgroup_active_workers".to_string(),
+                    vec!["metered".to_string()],
+                    DebugValue::Gauge(3.0.into())
+                ),
+                (
+                    "waitgroup_workers_total".to_string(),
+                    vec!["metered".to_string()],
+                    DebugValue::Counter(4)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn worker_id() {
+        let wg = WaitGroup::new();
+        let w = wg.named_worker("a");
+        let ids = [w.id(), w.clone().id(), wg.worker().id()];
+        assert!(ids[0] != ids[1] && ids[1] != ids[2] && ids[0] != ids[2]);
+        assert_eq!(wg.pending()[0].id(), w.id());
+        assert_eq!(w.handoff().id(), ids[0]);
+    }
+
+    #[test]
+    fn worker_meta() {
+        let wg = WaitGroup::new();
+        let a = wg.worker_with(1u32);
+        let _b = wg.worker_with("tenant-b");
+        assert_eq!(a.clone().meta::<u32>(), Some(&1));
+        assert_eq!(a.meta::<String>(), None);
+
+        assert_eq!(wg.pending_meta::<u32>(), vec![1]);
+        assert_eq!(wg.pending_meta::<&str>(), vec!["tenant-b"]);
+        drop(a);
+        assert!(wg.pending_meta::<u32>().is_empty());
+    }
+
+    #[async_std::test]
+    async fn wait_for_tag() {
+        let wg = WaitGroup::new();
+        let critical = wg.tagged_worker("critical");
+        let best_effort = wg.tagged_worker("best-effort");
+        let sub = critical.clone();
+        assert_eq!(sub.tag(), Some("critical"));
+
+        let waiter = task::spawn(wg.wait_for_tag("critical"));
+        drop(critical);
+        task::sleep(Duration::from_millis(1)).await;
+        drop(sub);
+        waiter.await;
+        assert_eq!(wg.workers(), 1);
+        assert_eq!(wg.pending()[0].tag(), best_effort.tag());
+    }
+
+    #[async_std::test]
+    async fn worker_weighted() {
+        let wg = WaitGroup::new();
+        let batch = wg.worker_weighted(3);
+        let single = wg.worker();
+        assert_eq!(wg.workers(), 4);
+
+        let halves = batch.clone().split(2);
+        assert_eq!(halves[0].weight(), 3);
+        assert_eq!(wg.workers(), 10);
+        drop(halves);
+        drop(batch);
+        assert_eq!(wg.workers(), 1);
+
+        let waiter = task::spawn(wg.wait());
+        single.done();
+        waiter.await;
+    }
+
+    #[async_std::test]
+    async fn max_workers() {
+        let wg = WaitGroup::builder().max_workers(2).build();
+        let first = wg.acquire().await;
+        let _second = wg.acquire().await;
+
+        let third = task::spawn(wg.acquire());
+        task::sleep(Duration::from_millis(1)).await;
+        assert_eq!(wg.workers(), 2);
+        drop(first);
+        let _third = third.await;
+        assert_eq!(wg.workers(), 2);
+        assert_eq!(wg.inner.peak.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn try_worker() {
+        let wg = WaitGroup::builder().max_workers(1).build();
+        let w = wg.try_worker().unwrap();
+        assert!(wg.try_worker().is_none());
+        drop(w);
+        assert!(wg.try_worker().is_some());
+        assert!(WaitGroup::new().try_worker().is_some());
+    }
+
+    #[async_std::test]
+    async fn worker_with_deadline() {
+        let wg = WaitGroup::new();
+        let w = wg.worker_with_deadline(Instant::now());
+        task::sleep(Duration::from_millis(1)).await;
+        drop(w);
+        assert_eq!(wg.overruns(), 1);
+
+        let wg = WaitGroup::builder().release_overdue(true).build();
+        let stuck = wg.worker_with_deadline(Instant::now() + Duration::from_millis(10));
+        let w = wg.worker_with_deadline(Instant::now() + Duration::from_secs(60));
+        let sub = stuck.clone();
+        let waiter = task::spawn(wg.wait_with_stats());
+        w.done();
+        assert_eq!(waiter.await.overruns(), 2);
+        drop(stuck);
+        assert!(wg.pending()[0].deadline().is_some());
+        drop(sub);
+        assert_eq!(wg.workers(), 0);
+        assert!(wg.pending().is_empty());
+    }
+
+    #[test]
+    fn wrap_fn() {
+        let wg = WaitGroup::new();
+        let f = wg.wrap_fn(|| 42);
+        assert_eq!(wg.workers(), 1);
+        assert_eq!(thread::spawn(f).join().unwrap(), 42);
+        assert_eq!(wg.workers(), 0);
+
+        let f = wg.wrap_fn(|| panic!("worker panicked"));
+        assert!(thread::spawn(f).join().is_err());
+        assert_eq!(wg.workers(), 0);
+        assert_eq!(wg.panicked(), 1);
+    }
+
+    #[async_std::test]
+    async fn panicked() {
+        let wg = WaitGroup::new();
+        let worker = wg.worker();
+        let waiter = task::spawn(wg.wait_with_stats());
+        let thread = thread::spawn(move || {
+            let _worker = worker;
+            panic!("worker panicked");
+        });
+        assert!(thread.join().is_err());
+        assert_eq!(waiter.await.panicked(), 1);
+
+        let error = wg.wait_result().await.unwrap_err();
+        assert!(error
+            .downcast_ref::<Panicked>()
+            .unwrap()
+            .message()
+            .is_none());
+        assert!(!wg.inner.is_cancelled());
+    }
+
+    #[async_std::test]
+    async fn cancel_on_panic() {
+        let wg = WaitGroup::builder().cancel_on_panic(true).build();
+        let sibling = task::spawn(wg.inner.wait_for(Until::Cancelled));
+        let f = wg.wrap_fn(|| panic!("worker panicked"));
+        assert!(thread::spawn(f).join().is_err());
+        sibling.await;
+    }
+
+    #[test]
+    fn complete_ok_err() {
+        let wg = WaitGroup::new();
+        let mut workers = wg.workers_batch(3).into_iter();
+        assert!(!workers.next().unwrap().complete_ok());
+        assert!(!workers.next().unwrap().complete_err());
+        assert!(workers.next().unwrap().complete_err());
+        assert_eq!((wg.succeeded(), wg.failed()), (1, 2));
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn worker_eq_hash() {
+        use std::collections::HashSet;
+
+        let wg = WaitGroup::new();
+        let a = wg.worker();
+        let b = a.clone();
+        assert!(a != b);
+        // ids are only unique within a group
+        assert!(a != WaitGroup::new().worker());
+
+        let mut workers = HashSet::new();
+        assert!(workers.insert(a));
+        assert!(workers.insert(b));
+        workers.retain(|w| w.id() != 0);
+        assert_eq!(wg.workers(), 1);
+    }
+
+    #[test]
+    fn touch() {
+        let wg = WaitGroup::new();
+        let mut alive = wg.worker();
+        let _wedged = wg.named_worker("wedged");
+        thread::sleep(Duration::from_millis(1));
+        alive.touch();
+        let info = |wg: &WaitGroup| {
+            let pending = wg.pending();
+            pending.into_iter().find(|info| info.id() == 0).unwrap()
+        };
+        let created = info(&wg).created();
+        assert!(created < info(&wg).last_seen());
+        thread::sleep(Duration::from_millis(10));
+        alive.touch();
+        assert_eq!(info(&wg).created(), created);
+        assert!(info(&wg).last_seen() > created + Duration::from_millis(10));
+
+        let stale = wg.stale(Duration::from_millis(5));
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].name(), Some("wedged"));
+        assert_eq!(stale[0].last_seen(), stale[0].created());
+        assert_eq!(wg.pending().len(), 2);
+    }
+
+    #[async_std::test]
+    async fn wait_result() {
+        let wg = WaitGroup::new();
+        let workers = wg.workers_batch(3);
+        let waiter = task::spawn(wg.wait_result());
+        for (i, w) in workers.into_iter().enumerate() {
+            w.fail(format!("worker {} failed", i));
+        }
+        assert_eq!(waiter.await.unwrap_err().to_string(), "worker 0 failed");
+        assert_eq!(wg.failed(), 3);
+        assert!(wg.wait_result().await.is_ok());
+    }
+
+    #[async_std::test]
+    async fn wait_errors() {
+        let wg = WaitGroup::builder().collect_errors(true).build();
+        for w in wg.workers_batch(3) {
+            w.fail("failed");
+        }
+        wg.worker().done();
+        let errors = wg.wait_errors().await.unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors.to_string(),
+            "3 workers failed, the first with: failed"
+        );
+        assert!(wg.wait_errors().await.is_ok());
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[async_std::test]
+    async fn wait_anyhow() {
+        let wg = WaitGroup::new();
+        wg.worker().fail(anyhow::anyhow!("failed"));
+        assert_eq!(wg.wait_anyhow().await.unwrap_err().to_string(), "failed");
+    }
+
+    #[async_std::test]
+    async fn cancel() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        let token = w.cancel_token();
+        let worker = task::spawn(async move {
+            token.cancelled().await;
+            assert!(w.is_cancelled());
+            drop(w);
+        });
+        task::sleep(Duration::from_millis(1)).await;
+        assert!(!wg.is_complete());
+
+        assert!(!wg.is_cancelled());
+        assert!(wg.cancel());
+        assert!(wg.is_cancelled());
+        assert!(!wg.cancel_token().cancel());
+        wg.wait().await;
+        worker.await;
+    }
+
+    #[cfg(feature = "tokio-util")]
+    #[async_std::test]
+    async fn cancellation_token() {
+        let parent = tokio_util::sync::CancellationToken::new();
+        let wg = WaitGroup::builder()
+            .cancellation_token(parent.clone())
+            .build();
+        let child = wg.child_token();
+        let w = wg.worker();
+        let worker = task::spawn(async move {
+            w.cancelled().await;
+            drop(w);
+        });
+        task::sleep(Duration::from_millis(1)).await;
+
+        parent.cancel();
+        worker.await;
+        assert!(wg.is_cancelled());
+        assert!(child.is_cancelled());
+        wg.wait().await;
+    }
+
+    #[test]
+    fn link_child() {
+        let parent = WaitGroup::new();
+        let child = WaitGroup::new();
+        let grandchild = WaitGroup::new();
+        parent.link_child(&child);
+        child.link_child(&grandchild);
+        parent.link_child(&WaitGroup::new());
+
+        assert!(!child.cancel_token().is_cancelled());
+        parent.cancel();
+        assert!(child.is_cancelled());
+        assert!(grandchild.is_cancelled());
+
+        let late = WaitGroup::new();
+        parent.link_child(&late);
+        assert!(late.is_cancelled());
+    }
+
+    #[async_std::test]
+    async fn subgroup() {
+        let wg = WaitGroup::new();
+        let stage = wg.subgroup();
+        assert_eq!(wg.workers(), 1);
+        let w = stage.worker();
+        let waiter = task::spawn(wg.wait());
+        task::sleep(Duration::from_millis(1)).await;
+        assert_eq!(wg.workers(), 1);
+        drop(w);
+        waiter.await;
+
+        // an unused subgroup is released when dropped
+        let stage = wg.subgroup();
+        wg.cancel();
+        assert!(stage.is_cancelled());
+        drop(stage);
+        assert!(wg.is_complete());
+    }
+
+    #[test]
+    fn subgroup_with() {
+        let wg = Arc::new(WaitGroup::new());
+        let seen = Arc::new(AtomicUsize::new(usize::MAX));
+        let stage = {
+            let (wg, seen) = (wg.clone(), seen.clone());
+            wg.clone()
+                .subgroup_with(move || seen.store(wg.workers(), Ordering::SeqCst))
+        };
+        let w = stage.worker();
+        drop(w);
+        // the hook ran while the parent still counted the subgroup
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+        assert!(wg.is_complete());
+
+        let seen = Arc::new(AtomicUsize::new(0));
+        let hook = seen.clone();
+        drop(wg.subgroup_with(move || hook.store(1, Ordering::SeqCst)));
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn named_groups() {
+        let find = || {
+            WaitGroup::named_groups()
+                .into_iter()
+                .find(|group| group.name() == "test::named_groups")
+        };
+        let wg = WaitGroup::named("test::named_groups");
+        assert_eq!(wg.name(), Some("test::named_groups"));
+        let w = wg.worker();
+        assert_eq!(find().unwrap().workers(), 1);
+        drop(w);
+        assert_eq!(find().unwrap().workers(), 0);
+        drop(wg);
+        assert!(find().is_none());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn spawn_tokio() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let wg = WaitGroup::new();
+            let handles: Vec<_> = (0..10).map(|i| wg.spawn(async move { i })).collect();
+            assert_eq!(wg.workers(), 10);
+            wg.wait().await;
+            for (i, handle) in handles.into_iter().enumerate() {
+                assert_eq!(handle.await.unwrap(), i);
+            }
+        });
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn spawn_in() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let wg = WaitGroup::new();
+            let mut set = tokio::task::JoinSet::new();
+            wg.spawn_in(&mut set, async { 1 });
+            wg.spawn_in(&mut set, std::future::pending()).abort();
+            assert_eq!(wg.workers(), 2);
+            let mut results = Vec::new();
+            while let Some(result) = set.join_next().await {
+                results.push(result.ok());
+            }
+            assert!(results.contains(&Some(1)) && results.contains(&None));
+            assert!(wg.is_complete());
+        });
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn spawn_local() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        tokio::task::LocalSet::new().block_on(&rt, async {
+            let wg = WaitGroup::new();
+            let count = std::rc::Rc::new(std::cell::Cell::new(0));
+            for _ in 0..10 {
+                let count = count.clone();
+                wg.spawn_local(async move { count.set(count.get() + 1) });
+            }
+            wg.wait().await;
+            assert_eq!(count.get(), 10);
+        });
+    }
+
+    #[cfg(feature = "futures-task")]
+    #[test]
+    fn spawn_with() {
+        let mut pool = futures_executor::LocalPool::new();
+        let spawner: &dyn futures_task::Spawn = &pool.spawner();
+        let wg = WaitGroup::new();
+        for _ in 0..10 {
+            wg.spawn_with(spawner, async {}).unwrap();
+        }
+        assert_eq!(wg.workers(), 10);
+        pool.run_until(wg.wait());
+    }
+
+    #[test]
+    fn spawn_thread() {
+        let wg = WaitGroup::new();
+        let ok = wg.spawn_thread(|| 42);
+        let panicked = wg.spawn_thread(|| panic!("thread panicked"));
+        wg.wait_blocking();
+        assert_eq!(ok.join().unwrap(), 42);
+        assert!(panicked.join().is_err());
+        assert_eq!(wg.panicked(), 1);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn spawn_rayon() {
+        let wg = WaitGroup::new();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let sum = Arc::new(AtomicUsize::new(0));
+        for i in 0..10 {
+            let sum = sum.clone();
+            wg.spawn_rayon_in(&pool, move || {
+                sum.fetch_add(i, Ordering::SeqCst);
+            });
+        }
+        wg.wait_blocking();
+        assert_eq!(sum.load(Ordering::SeqCst), 45);
+    }
+
+    #[cfg(feature = "async-std")]
+    #[async_std::test]
+    async fn spawn_async_std() {
+        let wg = WaitGroup::new();
+        let handle = wg.spawn_async_std(async { 42 });
+        wg.wait().await;
+        assert_eq!(handle.await, 42);
+    }
+
+    #[cfg(feature = "smol")]
+    #[test]
+    fn spawn_smol() {
+        smol::block_on(async {
+            let wg = WaitGroup::new();
+            wg.spawn_smol(async {}).detach();
+            let task = wg.spawn_smol(async { 42 });
+            wg.wait().await;
+            assert_eq!(task.await, 42);
+
+            // a cancelled task releases its worker
+            wg.spawn_smol(std::future::pending::<()>()).cancel().await;
+            assert!(wg.is_complete());
+        });
+    }
+
+    #[test]
+    fn remaining() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        let sub = w.clone();
+        assert_eq!(sub.remaining(), 2);
+        drop(sub);
+        assert_eq!(w.remaining(), 1);
+    }
+
+    #[async_std::test]
+    async fn detach() {
+        let wg = WaitGroup::new();
+        let daemon = wg.worker();
+        let w = wg.worker();
+        let any = wg.wait_any();
+        daemon.detach();
+        assert_eq!(wg.workers(), 1);
+        assert!(!any.until.is_reached(&any.inner));
+        w.done();
+        any.await;
+    }
+
+    #[test]
+    fn weak_worker() {
+        let wg = WaitGroup::new();
+        let weak = wg.worker().downgrade();
+        assert!(wg.is_complete());
+
+        let w = weak.upgrade().unwrap();
+        assert_eq!(wg.workers(), 1);
+        drop(wg);
+        drop(w);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn is_complete() {
+        let wg = WaitGroup::new();
+        assert!(wg.is_complete());
+        let w = wg.worker();
+        let w2 = w.clone();
+        assert!(!wg.is_complete());
+        drop(w);
+        assert!(!wg.is_complete());
+        drop(w2);
+        assert!(wg.is_complete());
+    }
+
+    #[test]
+    fn poll_wait() {
+        let mut wg = WaitGroup::new();
+        let w = wg.worker();
+        let woken = Arc::new(AtomicBool::new(false));
+        let waker = Waker::from(Arc::new(FlagWaker(woken.clone())));
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(wg.poll_wait(&mut cx).is_pending());
+        drop(w);
+        assert!(woken.load(Ordering::SeqCst));
+        assert!(wg.poll_wait(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn poll_wait_next_generation() {
+        let mut wg = WaitGroup::new();
+        let w = wg.worker();
+        let waker = Waker::from(Arc::new(FlagWaker(Arc::default())));
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(wg.poll_wait(&mut cx).is_pending());
+        // the round drains and the next one starts before the next poll
+        drop(w);
+        let next = wg.worker();
+        assert!(wg.poll_wait(&mut cx).is_ready());
+        // the next poll waits for the new round
+        assert!(wg.poll_wait(&mut cx).is_pending());
+        drop(next);
+        assert!(wg.poll_wait(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn recreate_wait() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        let woken = Arc::new(AtomicBool::new(false));
+        let waker = Waker::from(Arc::new(FlagWaker(woken.clone())));
+        let mut cx = Context::from_waker(&waker);
+
+        // what a `select!` loop does when another branch wins every time
+        for _ in 0..1000 {
+            let mut wait = wg.wait();
+            assert!(Pin::new(&mut wait).poll(&mut cx).is_pending());
+        }
+        assert_eq!(wg.inner.waiters.len(), 0);
+
+        let mut wait = wg.wait();
+        assert!(Pin::new(&mut wait).poll(&mut cx).is_pending());
+        drop(w);
+        assert!(woken.load(Ordering::SeqCst));
+        assert!(Pin::new(&mut wait).poll(&mut cx).is_ready());
+        assert!(Pin::new(&mut wg.wait()).poll(&mut cx).is_ready());
+    }
+
+    struct FlagWaker(Arc<AtomicBool>);
+
+    impl Wake for FlagWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn blocking() {
+        let wg = WaitGroup::new();
+
+        for _ in 0..100 {
+            let w = wg.worker();
+            std::thread::spawn(move || {
+                drop(w);
+            });
+        }
+
+        wg.wait_blocking();
+    }
+
+    #[async_std::test]
+    async fn timeout() {
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        let res = wg.wait_timeout(Duration::from_millis(10)).await;
+        assert_eq!(res.unwrap_err().workers(), 1);
+        drop(w);
+
+        let wg = WaitGroup::new();
+        let w = wg.worker();
+        task::spawn(async move {
+            task::sleep(Duration::from_millis(10)).await;
+            drop(w);
+        });
+        let res = wg.wait_timeout(Duration::from_secs(10)).await;
+        assert_eq!(res, Ok(()));
+    }
+
+    #[async_std::test]
+    async fn deadline() {
+        let wg = WaitGroup::new();
+        let _workers: Vec<_> = (0..3).map(|_| wg.worker()).collect();
+        let deadline = Instant::now() + Duration::from_millis(10);
+        let elapsed = wg.wait_until(deadline).await.unwrap_err();
+        assert!(Instant::now() >= deadline);
+        assert_eq!(elapsed.workers(), 3);
     }
 }
@@ -0,0 +1,257 @@
+use crate::waiters::LocalWaiters;
+use std::cell::Cell;
+use std::future::{Future, IntoFuture};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+/// A [`WaitGroup`](crate::WaitGroup) for single-threaded executors, without any atomic
+/// operations or locks.
+///
+/// It has the counting API of `WaitGroup`: workers, [`add`](Self::add) and
+/// [`done`](Self::done), and [`wait`](Self::wait) for the current round of workers. Names,
+/// metadata, weights, deadlines, errors and the other waits are only on `WaitGroup`.
+///
+/// Neither the group nor its workers can be sent to another thread:
+///
+/// ```compile_fail
+/// let wg = waitgroup::LocalWaitGroup::new();
+/// let w = wg.worker();
+/// std::thread::spawn(move || drop(w));
+/// ```
+///
+/// ```rust
+/// use futures_executor::LocalPool;
+/// use futures_util::task::LocalSpawnExt;
+/// use waitgroup::LocalWaitGroup;
+///
+/// let mut pool = LocalPool::new();
+/// let wg = LocalWaitGroup::new();
+/// for _ in 0..10 {
+///     let w = wg.worker();
+///     pool.spawner()
+///         .spawn_local(async move {
+///             // do work...
+///             drop(w);
+///         })
+///         .unwrap();
+/// }
+/// pool.run_until(wg.wait());
+/// ```
+pub struct LocalWaitGroup {
+    inner: Rc<Inner>,
+}
+
+/// A worker of a [`LocalWaitGroup`].
+pub struct LocalWorker {
+    inner: Rc<Inner>,
+}
+
+/// Future returned by [`LocalWaitGroup::wait`].
+pub struct LocalWait {
+    inner: Rc<Inner>,
+    generation: usize,
+    key: Option<usize>,
+}
+
+struct Inner {
+    count: Cell<usize>,
+    // bumped every time the count drops to zero
+    generation: Cell<usize>,
+    waiters: LocalWaiters,
+}
+
+impl Inner {
+    /// Removes one from the count, waking the waiters if it drops to zero.
+    fn release(&self) {
+        let count = self.count.get() - 1;
+        self.count.set(count);
+        if count == 0 {
+            self.generation.set(self.generation.get() + 1);
+            self.waiters.wake_all();
+        }
+    }
+}
+
+impl LocalWaitGroup {
+    pub fn new() -> Self {
+        Self {
+            inner: Rc::new(Inner {
+                count: Cell::new(0),
+                generation: Cell::new(0),
+                waiters: LocalWaiters::new(),
+            }),
+        }
+    }
+
+    /// Creates a worker.
+    pub fn worker(&self) -> LocalWorker {
+        self.inner.count.set(self.inner.count.get() + 1);
+        LocalWorker {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Adds `n` to the count of active workers without creating [`LocalWorker`] handles.
+    ///
+    /// Each unit must be released by a call to [`done`](Self::done).
+    pub fn add(&self, n: usize) {
+        self.inner.count.set(self.inner.count.get() + n);
+    }
+
+    /// Marks one unit added by [`add`](Self::add) as finished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no active workers.
+    pub fn done(&self) {
+        assert!(
+            self.inner.count.get() > 0,
+            "negative LocalWaitGroup counter"
+        );
+        self.inner.release();
+    }
+
+    /// Gets the number of active workers.
+    pub fn workers(&self) -> usize {
+        self.inner.count.get()
+    }
+
+    /// Returns `true` if all workers have finished, without waiting.
+    pub fn is_complete(&self) -> bool {
+        self.workers() == 0
+    }
+
+    /// Waits for all workers to finish.
+    ///
+    /// Like [`WaitGroup::wait`](crate::WaitGroup::wait), a round that drains completely is
+    /// never missed, even if new workers are added before the future is polled.
+    pub fn wait(&self) -> LocalWait {
+        LocalWait {
+            inner: self.inner.clone(),
+            generation: self.inner.generation.get(),
+            key: None,
+        }
+    }
+}
+
+impl IntoFuture for LocalWaitGroup {
+    type Output = ();
+    type IntoFuture = LocalWait;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.wait()
+    }
+}
+
+impl Default for LocalWaitGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalWorker {
+    /// Marks the worker as finished, returning `true` if this was the last active worker.
+    pub fn done(self) -> bool {
+        // nothing else can release a worker in between, as the group never leaves this thread
+        let last = self.is_last();
+        drop(self);
+        last
+    }
+
+    /// Returns `true` if this is the only active worker in the group.
+    pub fn is_last(&self) -> bool {
+        self.remaining() == 1
+    }
+
+    /// Gets the number of active workers in the group, including this one.
+    pub fn remaining(&self) -> usize {
+        self.inner.count.get()
+    }
+}
+
+impl Clone for LocalWorker {
+    fn clone(&self) -> Self {
+        self.inner.count.set(self.inner.count.get() + 1);
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl Drop for LocalWorker {
+    fn drop(&mut self) {
+        self.inner.release();
+    }
+}
+
+impl Future for LocalWait {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if this.inner.count.get() == 0 || this.inner.generation.get() != this.generation {
+            this.inner.waiters.remove(&mut this.key);
+            return Poll::Ready(());
+        }
+        // nothing can release a worker in between, as the group never leaves this thread
+        this.inner.waiters.register(&mut this.key, cx.waker(), ());
+        Poll::Pending
+    }
+}
+
+impl Drop for LocalWait {
+    fn drop(&mut self) {
+        self.inner.waiters.remove(&mut self.key);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures_executor::LocalPool;
+    use futures_util::task::LocalSpawnExt;
+
+    #[test]
+    fn local() {
+        let mut pool = LocalPool::new();
+        let wg = LocalWaitGroup::new();
+        let done = Rc::new(Cell::new(0));
+        for _ in 0..10 {
+            let (w, done) = (wg.worker(), done.clone());
+            let task = async move {
+                done.set(done.get() + 1);
+                drop(w);
+            };
+            pool.spawner().spawn_local(task).unwrap();
+        }
+        // two waiters at once, each with its own key
+        let a = wg.wait();
+        let b = wg.wait();
+        assert_eq!(wg.workers(), 10);
+        pool.run_until(futures_util::future::join(a, b));
+        assert_eq!(done.get(), 10);
+        assert!(wg.is_complete());
+    }
+
+    #[test]
+    fn counter() {
+        let mut pool = LocalPool::new();
+        let wg = LocalWaitGroup::new();
+        wg.add(2);
+        let w = wg.worker();
+        assert!(!w.is_last());
+        wg.done();
+        wg.done();
+        assert!(w.is_last());
+
+        // the round drains before the wait is polled, while the next one starts
+        let wait = wg.wait();
+        assert!(w.done());
+        let next = wg.worker();
+        pool.run_until(wait);
+        assert!(!wg.is_complete());
+        assert!(next.done());
+        pool.run_until(wg.into_future());
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// Callbacks for the lifecycle of a group's workers, see [`WaitGroup::with_observer`].
+///
+/// The callbacks run synchronously on the thread that creates or releases the worker, so
+/// they should be quick. Every method does nothing by default.
+///
+/// Every created worker is dropped exactly once. A worker passed to
+/// [`Worker::handoff`](crate::Worker::handoff) keeps its id and is reported as the same
+/// worker, one passed to [`Worker::split`](crate::Worker::split) is dropped and replaced by
+/// the new workers.
+///
+/// [`WaitGroup::with_observer`]: crate::WaitGroup::with_observer
+pub trait WaitGroupObserver: Send + Sync + 'static {
+    /// Called for every new worker, before it is named or tagged.
+    fn on_worker_created(&self, id: u64) {
+        let _ = id;
+    }
+
+    /// Called when a worker finishes, `completed` is `false` if it was
+    /// [`detach`](crate::Worker::detach)ed or split.
+    fn on_worker_dropped(&self, id: u64, completed: bool) {
+        let _ = (id, completed);
+    }
+
+    /// Called every time the last active worker finishes, before waiters are woken.
+    fn on_complete(&self) {}
+}
+
+/// Keeps a handle to the observer's state, to read it while the group reports to it.
+impl<T: WaitGroupObserver + ?Sized> WaitGroupObserver for Arc<T> {
+    fn on_worker_created(&self, id: u64) {
+        (**self).on_worker_created(id);
+    }
+
+    fn on_worker_dropped(&self, id: u64, completed: bool) {
+        (**self).on_worker_dropped(id, completed);
+    }
+
+    fn on_complete(&self) {
+        (**self).on_complete();
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Observer(pub(crate) Arc<dyn WaitGroupObserver>);
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Observer")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::WaitGroup;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Events(Mutex<Vec<String>>);
+
+    impl WaitGroupObserver for Events {
+        fn on_worker_created(&self, id: u64) {
+            self.0.lock().unwrap().push(format!("created {}", id));
+        }
+
+        fn on_worker_dropped(&self, id: u64, completed: bool) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("dropped {} {}", id, completed));
+        }
+
+        fn on_complete(&self) {
+            self.0.lock().unwrap().push("complete".to_string());
+        }
+    }
+
+    #[test]
+    fn observer() {
+        let events = Arc::new(Events::default());
+        let wg = WaitGroup::with_observer(events.clone());
+        let first = wg.worker();
+        let second = wg.worker();
+        drop(first);
+        second.detach();
+        assert_eq!(
+            *events.0.lock().unwrap(),
+            [
+                "created 0",
+                "created 1",
+                "dropped 0 true",
+                "dropped 1 false",
+                "complete"
+            ]
+        );
+    }
+
+    #[test]
+    fn handoff_and_split() {
+        let events = Arc::new(Events::default());
+        let wg = WaitGroup::with_observer(events.clone());
+        let worker = wg.worker().handoff();
+        drop(worker.split(3));
+        let events = events.0.lock().unwrap();
+        let created = events.iter().filter(|e| e.starts_with("created")).count();
+        let dropped = events.iter().filter(|e| e.starts_with("dropped")).count();
+        assert_eq!((created, dropped), (4, 4));
+        assert_eq!(events[..2], ["created 0", "dropped 0 false"]);
+    }
+}
@@ -0,0 +1,136 @@
+use opentelemetry::global::{BoxedSpan, BoxedTracer};
+use opentelemetry::metrics::{Counter, Meter, UpDownCounter};
+use opentelemetry::trace::{Link, Span, SpanContext, Tracer};
+use opentelemetry::{global, Context, KeyValue};
+use std::sync::{Arc, Mutex};
+
+/// The OpenTelemetry instruments of a group.
+///
+/// The group's span lasts as long as the group, the span of a worker from its creation until
+/// it finishes.
+pub(crate) struct Telemetry {
+    tracer: Arc<BoxedTracer>,
+    span: Mutex<BoxedSpan>,
+    span_context: SpanContext,
+    // the `group` attribute, empty if the group is unnamed
+    attributes: Vec<KeyValue>,
+    active: UpDownCounter<i64>,
+    finished: Counter<u64>,
+}
+
+impl Telemetry {
+    /// Creates the instruments, from the global providers unless `tracer` or `meter` is set.
+    pub(crate) fn new(
+        name: Option<&str>,
+        tracer: Option<Arc<BoxedTracer>>,
+        meter: Option<Meter>,
+    ) -> Self {
+        let attributes: Vec<_> = name
+            .map(|name| KeyValue::new("group", name.to_string()))
+            .into_iter()
+            .collect();
+        let tracer = tracer.unwrap_or_else(|| Arc::new(global::tracer("waitgroup")));
+        let span = tracer
+            .span_builder("waitgroup")
+            .with_attributes(attributes.clone())
+            .start(&*tracer);
+        let meter = meter.unwrap_or_else(|| global::meter("waitgroup"));
+        Self {
+            tracer,
+            span_context: span.span_context().clone(),
+            span: Mutex::new(span),
+            attributes,
+            active: meter
+                .i64_up_down_counter("waitgroup.workers.active")
+                .with_description("Number of active workers.")
+                .build(),
+            finished: meter
+                .u64_counter("waitgroup.workers.finished")
+                .with_description("Number of finished workers.")
+                .build(),
+        }
+    }
+
+    /// Starts the span of a worker as a child of the current context, linked to the group.
+    pub(crate) fn worker_span(&self, id: u64) -> BoxedSpan {
+        let mut attributes = self.attributes.clone();
+        attributes.push(KeyValue::new("worker.id", id as i64));
+        self.tracer
+            .span_builder("worker")
+            .with_attributes(attributes)
+            .with_links(vec![Link::with_context(self.span_context.clone())])
+            .start_with_context(&*self.tracer, &Context::current())
+    }
+
+    pub(crate) fn added(&self, n: usize) {
+        self.active.add(n as i64, &self.attributes);
+    }
+
+    pub(crate) fn released(&self, n: usize, count: usize, completed: bool) {
+        self.active.add(-(n as i64), &self.attributes);
+        let mut attributes = self.attributes.clone();
+        attributes.push(KeyValue::new("completed", completed));
+        self.finished.add(n as u64, &attributes);
+        if count == 0 {
+            self.span.lock().unwrap().add_event("drained", Vec::new());
+        }
+    }
+}
+
+/// Ends the span of a finished worker.
+pub(crate) fn finish(span: &mut BoxedSpan, name: Option<&str>, completed: bool) {
+    if let Some(name) = name {
+        span.set_attribute(KeyValue::new("worker.name", name.to_string()));
+    }
+    span.set_attribute(KeyValue::new("completed", completed));
+    span.end();
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        self.span.get_mut().unwrap().end();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::WaitGroup;
+    use opentelemetry::global::BoxedTracer;
+    use opentelemetry::trace::{TraceContextExt, Tracer, TracerProvider};
+    use opentelemetry::Context;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+
+    #[test]
+    fn spans() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+
+        let cx = Context::current_with_span(provider.tracer("test").start("request"));
+        let request = cx.span().span_context().clone();
+        let wg = WaitGroup::builder()
+            .name("jobs")
+            .tracer(BoxedTracer::new(Box::new(provider.tracer("waitgroup"))))
+            .build();
+        let worker = {
+            let _guard = cx.clone().attach();
+            wg.named_worker("job")
+        };
+        drop(worker);
+        drop(wg);
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let worker = spans.iter().find(|span| span.name == "worker").unwrap();
+        assert_eq!(worker.name, "worker");
+        assert_eq!(worker.parent_span_id, request.span_id());
+        let group = &worker.links.links[0].span_context;
+        assert!(spans
+            .iter()
+            .any(|span| span.name == "waitgroup" && span.span_context == *group));
+        assert!(worker
+            .attributes
+            .iter()
+            .any(|kv| kv.key.as_str() == "worker.name" && kv.value.as_str() == "job"));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::waiters::Waiters;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+/// A reusable synchronization point with a varying number of parties, modeled after
+/// Java's `Phaser`.
+///
+/// Each phase advances when every registered party has arrived. Parties can register and
+/// deregister between phases.
+///
+/// The phaser counts arrivals, not parties: a party that arrives twice in a phase counts as
+/// two parties and can advance it before the others arrived.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use waitgroup::Phaser;
+/// use async_std::task;
+/// # task::block_on(async {
+/// let phaser = Arc::new(Phaser::new(4));
+/// let tasks: Vec<_> = (0..4)
+///     .map(|_| {
+///         let phaser = phaser.clone();
+///         task::spawn(async move {
+///             for round in 0..3 {
+///                 // fan out...
+///                 assert_eq!(phaser.arrive_and_await().await, round);
+///             }
+///         })
+///     })
+///     .collect();
+///
+/// for task in tasks {
+///     task.await;
+/// }
+/// assert_eq!(phaser.phase(), 3);
+/// # });
+/// ```
+pub struct Phaser {
+    state: Mutex<State>,
+    waiters: Waiters,
+}
+
+struct State {
+    phase: usize,
+    parties: usize,
+    arrived: usize,
+}
+
+/// Future returned by [`Phaser::arrive_and_await`].
+pub struct ArriveAndAwait<'a> {
+    phaser: &'a Phaser,
+    phase: usize,
+    key: Option<usize>,
+}
+
+impl Phaser {
+    /// Creates a phaser with `parties` registered parties.
+    pub fn new(parties: usize) -> Self {
+        Self {
+            state: Mutex::new(State {
+                phase: 0,
+                parties,
+                arrived: 0,
+            }),
+            waiters: Waiters::new(),
+        }
+    }
+
+    /// Gets the current phase number.
+    pub fn phase(&self) -> usize {
+        self.state.lock().unwrap().phase
+    }
+
+    /// Gets the number of registered parties.
+    pub fn parties(&self) -> usize {
+        self.state.lock().unwrap().parties
+    }
+
+    /// Gets the number of parties that arrived in the current phase.
+    pub fn arrived(&self) -> usize {
+        self.state.lock().unwrap().arrived
+    }
+
+    /// Adds a party and returns the current phase number.
+    pub fn register(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        state.parties += 1;
+        state.phase
+    }
+
+    /// Arrives without waiting for the others and returns the arrival phase number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no party is registered.
+    pub fn arrive(&self) -> usize {
+        self.arrive_inner(false)
+    }
+
+    /// Arrives and removes this party, returning the arrival phase number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no party is registered.
+    pub fn arrive_and_deregister(&self) -> usize {
+        self.arrive_inner(true)
+    }
+
+    /// Arrives and waits for the others, resolving to the arrival phase number.
+    ///
+    /// The arrival counts as soon as this is called, even if the future is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no party is registered.
+    pub fn arrive_and_await(&self) -> ArriveAndAwait<'_> {
+        ArriveAndAwait {
+            phaser: self,
+            phase: self.arrive_inner(false),
+            key: None,
+        }
+    }
+
+    fn arrive_inner(&self, deregister: bool) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let phase = state.phase;
+        if state.parties == 0 {
+            // without poisoning the lock, the phaser stays usable
+            drop(state);
+            panic!("no party is registered");
+        }
+        if deregister {
+            state.parties -= 1;
+        } else {
+            state.arrived += 1;
+        }
+        if state.parties > 0 && state.arrived >= state.parties {
+            state.arrived = 0;
+            state.phase += 1;
+            drop(state);
+            self.waiters.wake_all();
+        }
+        phase
+    }
+}
+
+impl Future for ArriveAndAwait<'_> {
+    type Output = usize;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if this.phaser.phase() == this.phase {
+            this.phaser.waiters.register(&mut this.key, cx.waker(), ());
+            // the phase may have advanced before the waker was registered
+            if this.phaser.phase() == this.phase {
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(this.phase)
+    }
+}
+
+impl Drop for ArriveAndAwait<'_> {
+    fn drop(&mut self) {
+        self.phaser.waiters.remove(&mut self.key);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[async_std::test]
+    async fn deregister() {
+        let phaser = Phaser::new(2);
+        let waiting = phaser.arrive_and_await();
+        assert_eq!(phaser.register(), 0);
+        assert_eq!(phaser.arrive(), 0);
+        assert_eq!(phaser.phase(), 0);
+
+        // the last outstanding party leaving advances the phase
+        assert_eq!(phaser.arrive_and_deregister(), 0);
+        assert_eq!(waiting.await, 0);
+        assert_eq!(phaser.phase(), 1);
+        assert_eq!(phaser.parties(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "no party is registered")]
+    fn arrive_without_parties() {
+        drop(Phaser::new(0).arrive_and_await());
+    }
+
+    #[test]
+    fn arrive_twice() {
+        let phaser = Phaser::new(2);
+        assert_eq!(phaser.arrive(), 0);
+        // the second arrival completes the phase, whichever party it comes from
+        assert_eq!(phaser.arrive(), 0);
+        assert_eq!(phaser.phase(), 1);
+        assert_eq!(phaser.arrived(), 0);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{Inner, Round};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Instant;
+
+pub(crate) type Meta = Arc<dyn Any + Send + Sync>;
+
+// every named group of the process, pruned as groups are dropped
+static GROUPS: Mutex<Vec<Weak<Inner>>> = Mutex::new(Vec::new());
+
+/// Diagnostic information about the workers that opted into it, such as named, tagged, or
+/// workers with metadata.
+///
+/// Plain workers never touch the registry, so they stay lock-free.
+#[derive(Default)]
+pub(crate) struct Registry {
+    entries: HashMap<u64, WorkerInfo>,
+    // number of active workers per tag
+    tags: HashMap<&'static str, usize>,
+}
+
+impl Registry {
+    pub(crate) fn insert(&mut self, info: WorkerInfo) {
+        if let Some(tag) = info.tag {
+            *self.tags.entry(tag).or_insert(0) += 1;
+        }
+        self.entries.insert(info.id, info);
+    }
+
+    pub(crate) fn remove(&mut self, id: u64) -> Option<WorkerInfo> {
+        let info = self.entries.remove(&id)?;
+        if let Some(tag) = info.tag {
+            if let Some(count) = self.tags.get_mut(tag) {
+                *count -= 1;
+                if *count == 0 {
+                    self.tags.remove(tag);
+                }
+            }
+        }
+        Some(info)
+    }
+
+    /// Marks the workers whose deadline passed by `now` as expired.
+    pub(crate) fn expire(&mut self, now: Instant) -> Expiry {
+        let mut expiry = Expiry::default();
+        for info in self.entries.values_mut().filter(|info| !info.expired) {
+            match info.deadline {
+                Some(deadline) if deadline <= now => {
+                    info.expired = true;
+                    expiry.workers += 1;
+                    if info.round.leave(info.weight) {
+                        expiry.units += info.weight;
+                    }
+                }
+                Some(deadline) => {
+                    expiry.next = Some(expiry.next.map_or(deadline, |next| next.min(deadline)));
+                }
+                None => {}
+            }
+        }
+        expiry
+    }
+
+    /// Returns `true` if the worker `id` is listed and expired.
+    pub(crate) fn is_expired(&self, id: u64) -> bool {
+        self.entries.get(&id).map_or(false, |info| info.expired)
+    }
+
+    /// Removes the workers of a detached `round`.
+    pub(crate) fn detach(&mut self, round: &Arc<Round>) {
+        let ids: Vec<_> = self
+            .entries
+            .values()
+            .filter(|info| Arc::ptr_eq(&info.round, round))
+            .map(|info| info.id)
+            .collect();
+        for id in ids {
+            self.remove(id);
+        }
+    }
+
+    /// Gets the number of active workers with `tag`.
+    pub(crate) fn tagged(&self, tag: &str) -> usize {
+        self.tags.get(tag).copied().unwrap_or(0)
+    }
+
+    pub(crate) fn touch(&mut self, id: u64, now: Instant) {
+        if let Some(info) = self.entries.get_mut(&id) {
+            info.last_seen = now;
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<WorkerInfo> {
+        self.filter(|_| true)
+    }
+
+    /// Gets the workers last seen before `cutoff`, oldest first.
+    pub(crate) fn stale(&self, cutoff: Instant) -> Vec<WorkerInfo> {
+        self.filter(|info| info.last_seen < cutoff)
+    }
+
+    fn filter(&self, f: impl Fn(&WorkerInfo) -> bool) -> Vec<WorkerInfo> {
+        let mut infos: Vec<_> = self
+            .entries
+            .values()
+            .filter(|info| f(info))
+            .cloned()
+            .collect();
+        infos.sort_by_key(|info| info.id);
+        infos
+    }
+}
+
+/// The workers that expired in a call to `Registry::expire`.
+#[derive(Default)]
+pub(crate) struct Expiry {
+    pub(crate) workers: usize,
+    pub(crate) units: usize,
+    // the earliest deadline that has not passed yet
+    pub(crate) next: Option<Instant>,
+}
+
+/// A snapshot of a named group, as returned by
+/// [`WaitGroup::named_groups`](crate::WaitGroup::named_groups).
+#[derive(Debug, Clone)]
+pub struct GroupInfo {
+    name: Arc<str>,
+    workers: usize,
+    cancelled: bool,
+}
+
+impl GroupInfo {
+    /// Gets the name of the group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the number of active workers of the group.
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Returns `true` if the group was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+pub(crate) fn register_group(inner: &Arc<Inner>) {
+    let mut groups = GROUPS.lock().unwrap();
+    groups.retain(|group| group.strong_count() > 0);
+    groups.push(Arc::downgrade(inner));
+}
+
+pub(crate) fn named_groups() -> Vec<GroupInfo> {
+    let mut groups = GROUPS.lock().unwrap();
+    groups.retain(|group| group.strong_count() > 0);
+    groups
+        .iter()
+        .filter_map(Weak::upgrade)
+        .filter_map(|inner| {
+            Some(GroupInfo {
+                name: inner.name.clone()?,
+                workers: inner.workers(),
+                cancelled: inner.is_cancelled(),
+            })
+        })
+        .collect()
+}
+
+/// A snapshot of an active worker, as returned by [`WaitGroup::pending`](crate::WaitGroup::pending).
+#[derive(Debug, Clone)]
+pub struct WorkerInfo {
+    pub(crate) id: u64,
+    pub(crate) name: Option<Arc<str>>,
+    pub(crate) tag: Option<&'static str>,
+    pub(crate) meta: Option<Meta>,
+    pub(crate) created: Instant,
+    pub(crate) last_seen: Instant,
+    pub(crate) deadline: Option<Instant>,
+    pub(crate) weight: usize,
+    // whether the units of the worker were released when its deadline passed
+    pub(crate) expired: bool,
+    pub(crate) round: Arc<Round>,
+}
+
+impl WorkerInfo {
+    /// Gets the id of the worker, see [`Worker::id`](crate::Worker::id).
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Gets the label of the worker, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Gets the tag of the worker, if it has one.
+    pub fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
+
+    /// Gets the metadata of the worker, if it has some of type `T`.
+    pub fn meta<T: Any>(&self) -> Option<&T> {
+        self.meta.as_ref()?.downcast_ref()
+    }
+
+    /// Gets the time the worker was created.
+    pub fn created(&self) -> Instant {
+        self.created
+    }
+
+    /// Gets the last time the worker called [`touch`](crate::Worker::touch), or the time it was
+    /// created if it never did.
+    pub fn last_seen(&self) -> Instant {
+        self.last_seen
+    }
+
+    /// Gets the deadline of the worker, if it has one.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+}
@@ -0,0 +1,20 @@
+use std::future::Future;
+
+/// Spawns `future` on the runtime selected by the enabled features.
+///
+/// With both `tokio` and `async-std` enabled, tokio is used from inside a tokio runtime and
+/// async-std everywhere else.
+pub(crate) fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(feature = "tokio")]
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn(future);
+        return;
+    }
+    #[cfg(feature = "async-std")]
+    async_std::task::spawn(future);
+    #[cfg(not(feature = "async-std"))]
+    tokio::spawn(future);
+}
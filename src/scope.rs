@@ -0,0 +1,418 @@
+use crate::{handle, rt, Inner, TrackedHandle, Until, WaitGroup, WaitGroupFuture, Worker};
+use pin_project_lite::pin_project;
+use std::any::Any;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// A handle to spawn tasks inside [`WaitGroup::scope`].
+///
+/// Every task spawned through it while the scope is running finishes before the scope
+/// resolves.
+#[derive(Clone)]
+pub struct Scope {
+    inner: Arc<Inner>,
+}
+
+pin_project! {
+    /// Future returned by [`WaitGroup::scope`].
+    pub struct ScopeFuture<F: Future> {
+        #[pin]
+        body: F,
+        group: WaitGroup,
+        // keeps the scope open while the body runs
+        worker: Option<Worker>,
+        output: Option<F::Output>,
+        wait: Option<WaitGroupFuture>,
+    }
+}
+
+/// A handle to spawn fallible tasks inside [`WaitGroup::nursery`].
+///
+/// The first task to fail or panic cancels the others.
+pub struct TaskScope<E> {
+    inner: Arc<Inner>,
+    failure: Arc<Mutex<Option<Failure<E>>>>,
+}
+
+enum Failure<E> {
+    Error(E),
+    Panic(Box<dyn Any + Send>),
+}
+
+pin_project! {
+    /// Future returned by [`WaitGroup::nursery`].
+    pub struct Nursery<F, T, E> {
+        #[pin]
+        body: F,
+        group: WaitGroup,
+        failure: Arc<Mutex<Option<Failure<E>>>>,
+        worker: Option<Worker>,
+        output: Option<T>,
+        wait: Option<WaitGroupFuture>,
+        // cancels the tasks if the nursery is dropped before it resolves
+        guard: CancelGuard,
+    }
+}
+
+pin_project! {
+    struct NurseryTask<F, E> {
+        #[pin]
+        future: Option<F>,
+        cancelled: WaitGroupFuture,
+        worker: Option<Worker>,
+        failure: Arc<Mutex<Option<Failure<E>>>>,
+    }
+}
+
+struct CancelGuard(Option<Arc<Inner>>);
+
+/// A handle to spawn tasks borrowing from the enclosing stack frame, inside
+/// [`WaitGroup::scope_blocking`].
+pub struct BorrowedScope<'env> {
+    inner: Arc<Inner>,
+    // invariant, so the scope cannot be coerced to spawn futures that borrow less
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+// the future is declared first so it is dropped before the worker is released
+struct BorrowedTask {
+    future: Option<BoxFuture>,
+    worker: Option<Worker>,
+}
+
+/// Blocks until the scope drains, also when the body unwinds.
+struct BlockOnDrop(WaitGroup);
+
+impl Scope {
+    /// Spawns `future` on the runtime and tracks it in the scope.
+    ///
+    /// The returned handle can be used to get the output of the task or to abort it.
+    pub fn spawn<F>(&self, future: F) -> TrackedHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (task, handle) = handle::pair(future, self.inner.acquire());
+        rt::spawn(task);
+        handle
+    }
+}
+
+impl<F: Future> ScopeFuture<F> {
+    pub(crate) fn new(body: impl FnOnce(Scope) -> F, group: WaitGroup) -> Self {
+        let worker = group.worker();
+        Self {
+            body: body(Scope {
+                inner: group.inner.clone(),
+            }),
+            group,
+            worker: Some(worker),
+            output: None,
+            wait: None,
+        }
+    }
+}
+
+impl<F: Future> Future for ScopeFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if this.worker.is_some() {
+            let output = match this.body.poll(cx) {
+                Poll::Ready(output) => output,
+                Poll::Pending => return Poll::Pending,
+            };
+            *this.output = Some(output);
+            this.worker.take();
+            *this.wait = Some(this.group.wait());
+        }
+        let wait = this.wait.as_mut().expect("polled after completion");
+        match Pin::new(wait).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(this.output.take().expect("polled after completion")),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<'env> BorrowedScope<'env> {
+    /// Spawns `future` on the runtime and tracks it in the scope.
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'env,
+    {
+        let future: Pin<Box<dyn Future<Output = ()> + Send + 'env>> = Box::pin(future);
+        // SAFETY: `scope_blocking` does not return before this worker is released, which
+        // only happens after the future has been dropped, so nothing it borrows for `'env`
+        // is used after `'env` ends.
+        let future: BoxFuture = unsafe { mem::transmute(future) };
+        rt::spawn(BorrowedTask {
+            future: Some(future),
+            worker: Some(self.inner.acquire()),
+        });
+    }
+}
+
+pub(crate) fn scope_blocking<'env, R>(
+    body: impl FnOnce(&BorrowedScope<'env>) -> R,
+    group: WaitGroup,
+) -> R {
+    let group = BlockOnDrop(group);
+    let scope = BorrowedScope {
+        inner: group.0.inner.clone(),
+        env: PhantomData,
+    };
+    body(&scope)
+}
+
+impl<E: Send + 'static> TaskScope<E> {
+    /// Spawns `future` on the runtime and tracks it in the nursery.
+    ///
+    /// An error or a panic of `future` cancels the nursery: tasks that have not finished are
+    /// dropped the next time they are polled.
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = Result<(), E>> + Send + 'static,
+    {
+        rt::spawn(NurseryTask {
+            future: Some(future),
+            cancelled: self.inner.wait_for(Until::Cancelled),
+            worker: Some(self.inner.acquire()),
+            failure: self.failure.clone(),
+        });
+    }
+}
+
+impl<E> TaskScope<E> {
+    /// Returns `true` once a task has failed.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+}
+
+impl<E> Clone for TaskScope<E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            failure: self.failure.clone(),
+        }
+    }
+}
+
+fn fail<E>(inner: &Inner, slot: &Mutex<Option<Failure<E>>>, failure: Failure<E>) {
+    let mut slot = slot.lock().unwrap();
+    if slot.is_none() {
+        *slot = Some(failure);
+        drop(slot);
+        inner.cancel();
+    }
+}
+
+impl<F, T, E> Nursery<F, T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    pub(crate) fn new(body: impl FnOnce(TaskScope<E>) -> F, group: WaitGroup) -> Self {
+        let failure = Arc::new(Mutex::new(None));
+        let worker = group.worker();
+        Self {
+            body: body(TaskScope {
+                inner: group.inner.clone(),
+                failure: failure.clone(),
+            }),
+            guard: CancelGuard(Some(group.inner.clone())),
+            group,
+            failure,
+            worker: Some(worker),
+            output: None,
+            wait: None,
+        }
+    }
+}
+
+impl<F, T, E> Future for Nursery<F, T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    type Output = Result<T, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if let Some(worker) = this.worker {
+            match this.body.poll(cx) {
+                Poll::Ready(Ok(output)) => *this.output = Some(output),
+                Poll::Ready(Err(error)) => fail(&worker.inner, this.failure, Failure::Error(error)),
+                Poll::Pending => return Poll::Pending,
+            }
+            this.worker.take();
+            *this.wait = Some(this.group.wait());
+        }
+        let wait = this.wait.as_mut().expect("polled after completion");
+        if Pin::new(wait).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        this.guard.0.take();
+        match this.failure.lock().unwrap().take() {
+            Some(Failure::Error(error)) => Poll::Ready(Err(error)),
+            Some(Failure::Panic(panic)) => panic::resume_unwind(panic),
+            None => Poll::Ready(Ok(this.output.take().expect("polled after completion"))),
+        }
+    }
+}
+
+impl<F, E> Future for NurseryTask<F, E>
+where
+    F: Future<Output = Result<(), E>>,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        let worker = match this.worker {
+            Some(worker) => worker,
+            None => return Poll::Ready(()),
+        };
+        let failure = if Pin::new(&mut *this.cancelled).poll(cx).is_ready() {
+            None
+        } else {
+            let future = this.future.as_mut().as_pin_mut().unwrap();
+            match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+                Ok(Poll::Pending) => return Poll::Pending,
+                Ok(Poll::Ready(Ok(()))) => None,
+                Ok(Poll::Ready(Err(error))) => Some(Failure::Error(error)),
+                Err(panic) => Some(Failure::Panic(panic)),
+            }
+        };
+        if let Some(failure) = failure {
+            fail(&worker.inner, this.failure, failure);
+        }
+        // drop the future before the worker, so a cancelled task is gone once the nursery
+        // resolves
+        this.future.set(None);
+        this.worker.take();
+        Poll::Ready(())
+    }
+}
+
+impl Future for BorrowedTask {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(future) = self.future.as_mut() {
+            if future.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            self.future = None;
+            self.worker = None;
+        }
+        Poll::Ready(())
+    }
+}
+
+impl Drop for BlockOnDrop {
+    fn drop(&mut self) {
+        self.0.wait_blocking();
+    }
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        if let Some(inner) = self.0.take() {
+            inner.cancel();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "async-std"))]
+mod test {
+    use super::*;
+    use async_std::task;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn scope() {
+        let wg = WaitGroup::new();
+        let done = Arc::new(AtomicUsize::new(0));
+        let output = wg
+            .scope(|s| {
+                let done = done.clone();
+                async move {
+                    for i in 0..10 {
+                        let done = done.clone();
+                        s.spawn(async move {
+                            task::sleep(Duration::from_millis(i)).await;
+                            done.fetch_add(1, Ordering::SeqCst);
+                        });
+                    }
+                    let handle = s.spawn(async { 42 });
+                    handle.await.unwrap()
+                }
+            })
+            .await;
+        assert_eq!(output, 42);
+        assert_eq!(done.load(Ordering::SeqCst), 10);
+        assert!(wg.is_complete());
+    }
+
+    #[async_std::test]
+    async fn nursery() {
+        let wg = WaitGroup::new();
+        let result: Result<(), &str> = wg
+            .nursery(|n| async move {
+                n.spawn(async {
+                    // cancelled by the failing sibling
+                    task::sleep(Duration::from_secs(60)).await;
+                    Ok(())
+                });
+                n.spawn(async { Err("connection refused") });
+                Ok(())
+            })
+            .await;
+        assert_eq!(result, Err("connection refused"));
+        assert!(wg.is_complete());
+
+        let value = wg.nursery(|_| async { Ok::<_, ()>(42) }).await;
+        assert_eq!(value, Ok(42));
+    }
+
+    #[test]
+    fn scope_blocking() {
+        let wg = WaitGroup::new();
+        let mut counts = vec![0; 4];
+        let total = AtomicUsize::new(0);
+        wg.scope_blocking(|s| {
+            for (i, count) in counts.iter_mut().enumerate() {
+                let total = &total;
+                s.spawn(async move {
+                    task::sleep(Duration::from_millis(i as u64)).await;
+                    *count += i;
+                    total.fetch_add(i, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(counts, [0, 1, 2, 3]);
+        assert_eq!(total.load(Ordering::SeqCst), 6);
+        assert!(wg.is_complete());
+    }
+
+    #[async_std::test]
+    async fn nursery_panic() {
+        let wg = WaitGroup::new();
+        let nursery = task::spawn(wg.nursery(|n| async move {
+            n.spawn(async { panic!("task panicked") });
+            Ok::<_, ()>(())
+        }));
+        let panic = panic::catch_unwind(AssertUnwindSafe(|| task::block_on(nursery)));
+        assert!(panic.is_err());
+        assert!(wg.is_complete());
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{Inner, TrackedFuture, WaitGroup, Waiter};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A tower layer that tracks every in-flight request with a worker of a [`WaitGroup`].
+///
+/// ```rust
+/// use tower_layer::Layer;
+/// use waitgroup::{WaitGroup, WaitGroupLayer};
+/// # struct Echo;
+/// # impl tower_service::Service<u32> for Echo {
+/// #     type Response = u32;
+/// #     type Error = ();
+/// #     type Future = std::future::Ready<Result<u32, ()>>;
+/// #     fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), ()>> {
+/// #         std::task::Poll::Ready(Ok(()))
+/// #     }
+/// #     fn call(&mut self, req: u32) -> Self::Future {
+/// #         std::future::ready(Ok(req))
+/// #     }
+/// # }
+/// # async_std::task::block_on(async {
+/// let wg = WaitGroup::new();
+/// let service = WaitGroupLayer::new(&wg).layer(Echo);
+/// // serve requests with `service`...
+///
+/// // on shutdown, stop accepting and drain the requests in flight
+/// wg.wait().await;
+/// # drop(service);
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct WaitGroupLayer {
+    group: Arc<Inner>,
+}
+
+/// A service that holds a worker while each response is pending.
+///
+/// Created by [`WaitGroupLayer`].
+#[derive(Clone)]
+pub struct WaitGroupService<S> {
+    inner: S,
+    group: Arc<Inner>,
+}
+
+impl WaitGroupLayer {
+    pub fn new(wg: &WaitGroup) -> Self {
+        Self {
+            group: wg.inner.clone(),
+        }
+    }
+}
+
+impl<S> Layer<S> for WaitGroupLayer {
+    type Service = WaitGroupService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        WaitGroupService {
+            inner,
+            group: self.group.clone(),
+        }
+    }
+}
+
+impl<S> WaitGroupService<S> {
+    pub fn new(inner: S, wg: &WaitGroup) -> Self {
+        Self {
+            inner,
+            group: wg.inner.clone(),
+        }
+    }
+
+    /// Gets a handle to wait for the requests in flight.
+    pub fn waiter(&self) -> Waiter {
+        Waiter(self.group.clone())
+    }
+
+    /// Gets the number of requests in flight.
+    pub fn in_flight(&self) -> usize {
+        self.group.workers()
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, R> Service<R> for WaitGroupService<S>
+where
+    S: Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = TrackedFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        // the worker is created before the inner service starts on the request
+        let worker = self.group.acquire();
+        TrackedFuture::new(self.inner.call(req), worker)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::task;
+    use std::future::{self, Ready};
+
+    struct Echo;
+
+    impl Service<u32> for Echo {
+        type Response = u32;
+        type Error = ();
+        type Future = Ready<Result<u32, ()>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: u32) -> Self::Future {
+            future::ready(Ok(req))
+        }
+    }
+
+    #[async_std::test]
+    async fn in_flight() {
+        let wg = WaitGroup::new();
+        let mut service = WaitGroupLayer::new(&wg).layer(Echo);
+        let a = service.call(1);
+        let b = service.call(2);
+        assert_eq!(service.in_flight(), 2);
+        assert_eq!(a.await, Ok(1));
+        drop(b);
+        service.waiter().wait().await;
+        task::spawn(service.call(3)).await.unwrap();
+        assert!(wg.is_complete());
+    }
+}
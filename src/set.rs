@@ -0,0 +1,191 @@
+use crate::{Inner, Until, WaitGroup};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// A collection of [`WaitGroup`]s that can be waited on together.
+///
+/// ```rust
+/// use waitgroup::{WaitGroup, WaitGroupSet};
+/// use async_std::task;
+/// # task::block_on(async {
+/// let shards: Vec<_> = (0..4).map(|_| WaitGroup::new()).collect();
+/// let mut set = WaitGroupSet::new();
+/// for shard in &shards {
+///     let w = shard.worker();
+///     task::spawn(async move {
+///         // do work...
+///         drop(w);
+///     });
+///     set.push(shard);
+/// }
+///
+/// set.wait_all().await;
+/// # });
+/// ```
+#[derive(Default)]
+pub struct WaitGroupSet {
+    groups: Vec<Arc<Inner>>,
+}
+
+/// Future returned by [`WaitGroupSet::wait_any_group`].
+pub struct WaitAnyGroup {
+    groups: Vec<(Arc<Inner>, Until, Option<usize>)>,
+}
+
+/// Future returned by [`WaitGroupSet::wait_all`].
+pub struct WaitAll {
+    groups: Vec<(Arc<Inner>, Until)>,
+    next: usize,
+    key: Option<usize>,
+}
+
+impl WaitGroupSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a group to the set and returns its index.
+    pub fn push(&mut self, group: &WaitGroup) -> usize {
+        self.groups.push(group.inner.clone());
+        self.groups.len() - 1
+    }
+
+    /// Gets the number of groups in the set.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Returns `true` if the set contains no groups.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Gets the number of active workers across all groups.
+    pub fn workers(&self) -> usize {
+        self.groups.iter().map(|inner| inner.workers()).sum()
+    }
+
+    /// Waits for every group in the set to finish.
+    ///
+    /// Only one group is watched at a time: the first one that has not finished yet.
+    pub fn wait_all(&self) -> WaitAll {
+        WaitAll {
+            groups: self
+                .groups
+                .iter()
+                .map(|inner| (inner.clone(), inner.drained()))
+                .collect(),
+            next: 0,
+            key: None,
+        }
+    }
+
+    /// Waits for the first group in the set to finish and resolves to its index.
+    ///
+    /// Resolves to `None` right away if the set is empty.
+    pub fn wait_any_group(&self) -> WaitAnyGroup {
+        WaitAnyGroup {
+            groups: self
+                .groups
+                .iter()
+                .map(|inner| (inner.clone(), inner.drained(), None))
+                .collect(),
+        }
+    }
+}
+
+impl Future for WaitAll {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        while let Some((inner, until)) = this.groups.get(this.next) {
+            if inner.poll_for(until, &mut this.key, cx).is_pending() {
+                return Poll::Pending;
+            }
+            this.next += 1;
+        }
+        Poll::Ready(())
+    }
+}
+
+impl Future for WaitAnyGroup {
+    type Output = Option<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.groups.is_empty() {
+            return Poll::Ready(None);
+        }
+        for (i, (inner, until, key)) in self.groups.iter_mut().enumerate() {
+            if inner.poll_for(until, key, cx).is_ready() {
+                return Poll::Ready(Some(i));
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for WaitAnyGroup {
+    fn drop(&mut self) {
+        for (inner, _, key) in self.groups.iter_mut() {
+            inner.waiters.remove(key);
+        }
+    }
+}
+
+impl Drop for WaitAll {
+    fn drop(&mut self) {
+        if let Some((inner, _)) = self.groups.get(self.next) {
+            inner.waiters.remove(&mut self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::task;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn wait_all() {
+        let groups: Vec<_> = (0..3).map(|_| WaitGroup::new()).collect();
+        let mut set = WaitGroupSet::new();
+        let mut workers = Vec::new();
+        for group in &groups {
+            set.push(group);
+            workers.push(group.worker());
+        }
+        assert_eq!(set.workers(), 3);
+
+        let waiter = task::spawn(set.wait_all());
+        // drain the groups back to front
+        while let Some(w) = workers.pop() {
+            task::sleep(Duration::from_millis(1)).await;
+            drop(w);
+        }
+        waiter.await;
+    }
+
+    #[async_std::test]
+    async fn wait_any_group() {
+        assert_eq!(WaitGroupSet::new().wait_any_group().await, None);
+
+        let groups: Vec<_> = (0..3).map(|_| WaitGroup::new()).collect();
+        let mut set = WaitGroupSet::new();
+        let mut workers: Vec<_> = groups
+            .iter()
+            .map(|group| {
+                set.push(group);
+                group.worker()
+            })
+            .collect();
+
+        let waiter = task::spawn(set.wait_any_group());
+        task::sleep(Duration::from_millis(1)).await;
+        workers.remove(1);
+        assert_eq!(waiter.await, Some(1));
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{CancelToken, Drain, DrainReport, Inner, Until, WaitGroup, WaitGroupFuture, Worker};
+use std::future::Future;
+#[cfg(all(feature = "signal", unix))]
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// A two-phase graceful shutdown.
+///
+/// The first phase raises the stop signal of the embedded [`WaitGroup`], the second one
+/// waits for its workers to finish, up to an optional timeout.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use waitgroup::Shutdown;
+/// use async_std::task;
+/// # task::block_on(async {
+/// let shutdown = Shutdown::new().timeout(Duration::from_secs(10));
+/// for i in 0..4 {
+///     let w = shutdown.named_worker(format!("listener-{}", i));
+///     task::spawn(async move {
+///         w.cancelled().await;
+///         drop(w);
+///     });
+/// }
+///
+/// let report = shutdown.shutdown().await;
+/// assert!(report.is_complete());
+/// # });
+/// ```
+pub struct Shutdown {
+    wg: WaitGroup,
+    timeout: Option<Duration>,
+    // stops the thread listening for signals
+    #[cfg(all(feature = "signal", unix))]
+    signals: Option<signal_hook::iterator::Handle>,
+}
+
+/// Future returned by [`Shutdown::wait`] and [`Shutdown::shutdown`].
+pub struct ShutdownWait {
+    inner: Arc<Inner>,
+    signal: WaitGroupFuture,
+    timeout: Option<Duration>,
+    drain: Option<Drain>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::with_group(WaitGroup::new())
+    }
+
+    /// Creates a shutdown for a configured group.
+    pub fn with_group(wg: WaitGroup) -> Self {
+        Self {
+            wg,
+            timeout: None,
+            #[cfg(all(feature = "signal", unix))]
+            signals: None,
+        }
+    }
+
+    /// Creates a shutdown that is triggered by `SIGINT`, as sent by Ctrl-C.
+    ///
+    /// ```no_run
+    /// use waitgroup::Shutdown;
+    /// # async_std::task::block_on(async {
+    /// let shutdown = Shutdown::on_ctrl_c()?;
+    /// // run the service with `shutdown.worker()`...
+    /// shutdown.wait().await;
+    /// # std::io::Result::Ok(())
+    /// # });
+    /// ```
+    #[cfg(all(feature = "signal", unix))]
+    pub fn on_ctrl_c() -> io::Result<Self> {
+        Self::new().on_signals(&[signal_hook::consts::SIGINT])
+    }
+
+    /// Creates a shutdown that is triggered by `SIGTERM`, as sent by service managers.
+    #[cfg(all(feature = "signal", unix))]
+    pub fn on_sigterm() -> io::Result<Self> {
+        Self::new().on_signals(&[signal_hook::consts::SIGTERM])
+    }
+
+    /// Triggers the shutdown from the first of `signals` on a background thread.
+    #[cfg(all(feature = "signal", unix))]
+    fn on_signals(mut self, signals: &[std::os::raw::c_int]) -> io::Result<Self> {
+        let mut signals = signal_hook::iterator::Signals::new(signals)?;
+        self.signals = Some(signals.handle());
+        let token = self.token();
+        std::thread::spawn(move || {
+            if signals.forever().next().is_some() {
+                token.cancel();
+            }
+        });
+        Ok(self)
+    }
+
+    /// Gives up waiting for the workers `timeout` after the stop signal.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn worker(&self) -> Worker {
+        self.wg.worker()
+    }
+
+    /// Creates a worker labeled with `name`, which shows up in the report if it does not
+    /// finish in time.
+    pub fn named_worker(&self, name: impl Into<Arc<str>>) -> Worker {
+        self.wg.named_worker(name)
+    }
+
+    /// Gets a handle to the stop signal.
+    pub fn token(&self) -> CancelToken {
+        self.wg.cancel_token()
+    }
+
+    /// Gets the embedded group.
+    pub fn group(&self) -> &WaitGroup {
+        &self.wg
+    }
+
+    /// Raises the stop signal, starting the shutdown.
+    pub fn trigger(&self) {
+        self.wg.cancel();
+    }
+
+    /// Waits for the stop signal, then for the workers to finish.
+    pub fn wait(&self) -> ShutdownWait {
+        ShutdownWait {
+            inner: self.wg.inner.clone(),
+            signal: self.wg.inner.wait_for(Until::Cancelled),
+            timeout: self.timeout,
+            drain: None,
+        }
+    }
+
+    /// Raises the stop signal and waits for the workers to finish.
+    pub fn shutdown(&self) -> ShutdownWait {
+        self.trigger();
+        self.wait()
+    }
+}
+
+#[cfg(all(feature = "signal", unix))]
+impl Drop for Shutdown {
+    fn drop(&mut self) {
+        if let Some(signals) = &self.signals {
+            signals.close();
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Future for ShutdownWait {
+    type Output = DrainReport;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if this.drain.is_none() {
+            if Pin::new(&mut this.signal).poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            this.drain = Some(Drain::new(&this.inner, this.timeout));
+        }
+        Pin::new(this.drain.as_mut().unwrap()).poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::task;
+
+    #[async_std::test]
+    async fn stragglers() {
+        let shutdown = Shutdown::new().timeout(Duration::from_millis(10));
+        let w = shutdown.worker();
+        task::spawn(async move {
+            w.cancelled().await;
+            drop(w);
+        });
+        let _stuck = shutdown.named_worker("stuck");
+
+        let waiter = task::spawn(shutdown.wait());
+        task::sleep(Duration::from_millis(1)).await;
+        shutdown.trigger();
+        let report = waiter.await;
+        assert_eq!(report.remaining(), 1);
+        assert_eq!(report.pending()[0].name(), Some("stuck"));
+    }
+
+    #[cfg(all(feature = "signal", unix))]
+    #[async_std::test]
+    async fn on_sigterm() {
+        let shutdown = Shutdown::on_sigterm().unwrap();
+        let waiter = task::spawn(shutdown.wait());
+        signal_hook::low_level::raise(signal_hook::consts::SIGTERM).unwrap();
+        assert!(waiter.await.is_complete());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{WaitGroup, WaitGroupFuture, Worker};
+use pin_project_lite::pin_project;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// A group of tasks whose outputs are collected.
+///
+/// ```rust
+/// use waitgroup::{Order, TaskGroup};
+/// use async_std::task;
+/// # task::block_on(async {
+/// let group = TaskGroup::with_order(Order::Spawn);
+/// for i in 0..10 {
+///     task::spawn(group.track(async move { i * 2 }));
+/// }
+///
+/// assert_eq!(group.wait().await, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+/// # });
+/// ```
+pub struct TaskGroup<T> {
+    wg: WaitGroup,
+    results: Arc<Mutex<Vec<(u64, T)>>>,
+    order: Order,
+}
+
+/// The order of the outputs collected by a [`TaskGroup`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    /// In the order the tasks completed.
+    #[default]
+    Completion,
+    /// In the order the tasks were tracked.
+    Spawn,
+}
+
+pin_project! {
+    /// Future returned by [`TaskGroup::track`].
+    pub struct TaskFuture<F, T> {
+        #[pin]
+        future: F,
+        worker: Option<Worker>,
+        results: Arc<Mutex<Vec<(u64, T)>>>,
+    }
+}
+
+/// Future returned by [`TaskGroup::wait`].
+pub struct TaskWait<T> {
+    wait: WaitGroupFuture,
+    results: Arc<Mutex<Vec<(u64, T)>>>,
+    order: Order,
+}
+
+impl<T> TaskGroup<T> {
+    /// Creates a group that collects outputs in completion order.
+    pub fn new() -> Self {
+        Self::with_order(Order::Completion)
+    }
+
+    pub fn with_order(order: Order) -> Self {
+        Self {
+            wg: WaitGroup::new(),
+            results: Arc::new(Mutex::new(Vec::new())),
+            order,
+        }
+    }
+
+    /// Wraps `future` so that the group waits for it and collects its output.
+    pub fn track<F>(&self, future: F) -> TaskFuture<F, T>
+    where
+        F: Future<Output = T>,
+    {
+        TaskFuture {
+            future,
+            worker: Some(self.wg.worker()),
+            results: self.results.clone(),
+        }
+    }
+
+    /// Gets the number of active tasks.
+    pub fn workers(&self) -> usize {
+        self.wg.workers()
+    }
+
+    /// Waits for all tasks to finish and resolves to their outputs.
+    ///
+    /// The outputs are taken out of the group, so each is only returned once.
+    pub fn wait(&self) -> TaskWait<T> {
+        TaskWait {
+            wait: self.wg.wait(),
+            results: self.results.clone(),
+            order: self.order,
+        }
+    }
+}
+
+impl<T> Default for TaskGroup<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F, T> Future for TaskFuture<F, T>
+where
+    F: Future<Output = T>,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let output = match this.future.poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return Poll::Pending,
+        };
+        if let Some(worker) = this.worker.take() {
+            // store the output before the worker is released, so `wait` sees it
+            this.results.lock().unwrap().push((worker.id(), output));
+        }
+        Poll::Ready(())
+    }
+}
+
+impl<T> Future for TaskWait<T> {
+    type Output = Vec<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if Pin::new(&mut self.wait).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let mut results = mem::take(&mut *self.results.lock().unwrap());
+        if self.order == Order::Spawn {
+            // worker ids are handed out in creation order
+            results.sort_by_key(|(id, _)| *id);
+        }
+        Poll::Ready(results.into_iter().map(|(_, output)| output).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::task;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn order() {
+        for order in [Order::Completion, Order::Spawn] {
+            let group = TaskGroup::with_order(order);
+            for i in 0..3u64 {
+                task::spawn(group.track(async move {
+                    task::sleep(Duration::from_millis(10 * (3 - i))).await;
+                    i
+                }));
+            }
+            let expected = match order {
+                Order::Completion => vec![2, 1, 0],
+                Order::Spawn => vec![0, 1, 2],
+            };
+            assert_eq!(group.wait().await, expected);
+        }
+    }
+}
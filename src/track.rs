@@ -0,0 +1,296 @@
+use crate::{Inner, WaitGroup, Worker};
+use futures_core::Stream;
+use pin_project_lite::pin_project;
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+pin_project! {
+    /// A future that holds a worker until it completes or is dropped.
+    ///
+    /// Created by [`WaitGroup::track`](crate::WaitGroup::track).
+    pub struct TrackedFuture<F> {
+        #[pin]
+        future: F,
+        worker: Option<Worker>,
+    }
+}
+
+pin_project! {
+    /// A future that holds a worker and catches a panic of the future it wraps.
+    ///
+    /// Created by [`WaitGroup::track_catching`](crate::WaitGroup::track_catching).
+    pub struct CatchUnwind<F> {
+        #[pin]
+        future: F,
+        worker: Option<Worker>,
+    }
+}
+
+pin_project! {
+    /// A stream that pairs each item with a worker.
+    ///
+    /// Created by [`WaitGroupStreamExt::tracked`].
+    pub struct Tracked<S> {
+        #[pin]
+        stream: S,
+        inner: Arc<Inner>,
+    }
+}
+
+/// An extension trait to track any future with a [`WaitGroup`].
+///
+/// ```rust
+/// use waitgroup::{WaitGroup, WaitGroupExt};
+/// use async_std::task;
+/// # task::block_on(async {
+/// let wg = WaitGroup::new();
+/// task::spawn(async {
+///     // do work...
+/// }.attach(&wg));
+/// wg.wait().await;
+/// # });
+/// ```
+pub trait WaitGroupExt: Future + Sized {
+    /// Makes the future hold a worker of `wg`, see [`WaitGroup::track`].
+    fn attach(self, wg: &WaitGroup) -> TrackedFuture<Self> {
+        wg.track(self)
+    }
+}
+
+impl<F: Future> WaitGroupExt for F {}
+
+/// An extension trait to track the items of any stream with a [`WaitGroup`].
+///
+/// ```rust
+/// use waitgroup::{WaitGroup, WaitGroupStreamExt};
+/// use async_std::{stream, task};
+/// use async_std::prelude::*;
+/// # task::block_on(async {
+/// let wg = WaitGroup::new();
+/// let mut items = stream::from_iter(0..10).tracked(&wg);
+/// while let Some((item, worker)) = items.next().await {
+///     task::spawn(async move {
+///         // process item...
+///         drop(worker);
+///     });
+/// }
+/// // every item has been processed
+/// wg.wait().await;
+/// # });
+/// ```
+pub trait WaitGroupStreamExt: Stream + Sized {
+    /// Yields each item together with a worker of `wg` that stays active while the item is
+    /// in flight.
+    fn tracked(self, wg: &WaitGroup) -> Tracked<Self> {
+        Tracked {
+            stream: self,
+            inner: wg.inner.clone(),
+        }
+    }
+}
+
+impl<S: Stream> WaitGroupStreamExt for S {}
+
+/// A stream that drives a set of tracked futures and yields their outputs as they complete.
+///
+/// Created by [`WaitGroup::track_unordered`](crate::WaitGroup::track_unordered).
+#[cfg(feature = "futures-util")]
+pub struct TrackUnordered<F> {
+    futures: futures_util::stream::FuturesUnordered<TrackedFuture<F>>,
+    inner: Arc<Inner>,
+}
+
+#[cfg(feature = "futures-util")]
+impl<F: Future> TrackUnordered<F> {
+    pub(crate) fn new(futures: impl IntoIterator<Item = F>, inner: Arc<Inner>) -> Self {
+        let mut this = Self {
+            futures: Default::default(),
+            inner,
+        };
+        this.extend(futures);
+        this
+    }
+
+    /// Adds a future, which holds a worker until it completes.
+    pub fn push(&mut self, future: F) {
+        self.futures
+            .push(TrackedFuture::new(future, self.inner.acquire()));
+    }
+
+    /// Gets the number of futures that have not completed yet.
+    pub fn len(&self) -> usize {
+        self.futures.len()
+    }
+
+    /// Returns `true` if no future is left.
+    pub fn is_empty(&self) -> bool {
+        self.futures.is_empty()
+    }
+}
+
+#[cfg(feature = "futures-util")]
+impl<F: Future> Extend<F> for TrackUnordered<F> {
+    fn extend<I: IntoIterator<Item = F>>(&mut self, futures: I) {
+        for future in futures {
+            self.push(future);
+        }
+    }
+}
+
+#[cfg(feature = "futures-util")]
+impl<F: Future> Stream for TrackUnordered<F> {
+    type Item = F::Output;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.futures).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.futures.size_hint()
+    }
+}
+
+impl<F> TrackedFuture<F> {
+    pub(crate) fn new(future: F, worker: Worker) -> Self {
+        Self {
+            future,
+            worker: Some(worker),
+        }
+    }
+}
+
+impl<F> CatchUnwind<F> {
+    pub(crate) fn new(future: F, worker: Worker) -> Self {
+        Self {
+            future,
+            worker: Some(worker),
+        }
+    }
+}
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = Result<F::Output, Box<dyn Any + Send>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let future = this.future;
+        let output = match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Ready(output)) => Ok(output),
+            Ok(Poll::Pending) => return Poll::Pending,
+            Err(panic) => Err(panic),
+        };
+        if let Some(worker) = this.worker.take() {
+            if let Err(panic) = &output {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned());
+                worker.inner.record_panic(message);
+            }
+        }
+        Poll::Ready(output)
+    }
+}
+
+impl<F: Future> Future for TrackedFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let output = match this.future.poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return Poll::Pending,
+        };
+        // release the worker before the task gets to run anything after the future
+        this.worker.take();
+        Poll::Ready(output)
+    }
+}
+
+impl<S: Stream> Stream for Tracked<S> {
+    type Item = (S::Item, Worker);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        match this.stream.poll_next(cx) {
+            Poll::Ready(Some(item)) => Poll::Ready(Some((item, this.inner.acquire()))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::prelude::*;
+    use async_std::{stream, task};
+
+    #[async_std::test]
+    async fn track() {
+        let wg = WaitGroup::new();
+        let task = task::spawn(wg.track(async { 42 }));
+        wg.wait().await;
+        assert_eq!(task.await, 42);
+
+        drop(wg.track(async {}));
+        assert!(wg.is_complete());
+    }
+
+    #[async_std::test]
+    async fn attach() {
+        let wg = WaitGroup::new();
+        let future = async { 42 }.attach(&wg);
+        assert_eq!(wg.workers(), 1);
+        assert_eq!(future.await, 42);
+        assert!(wg.is_complete());
+    }
+
+    #[async_std::test]
+    async fn track_catching() {
+        let wg = WaitGroup::new();
+        assert_eq!(wg.track_catching(async { 42 }).await.unwrap(), 42);
+        let task = task::spawn(wg.track_catching(async { panic!("worker panicked") }));
+        wg.wait().await;
+        assert!(task.await.is_err());
+        assert_eq!(wg.panicked(), 1);
+        let error = wg.wait_result().await.unwrap_err();
+        assert_eq!(error.to_string(), "worker panicked: worker panicked");
+    }
+
+    #[cfg(feature = "futures-util")]
+    #[async_std::test]
+    async fn track_unordered() {
+        let wg = WaitGroup::new();
+        let mut futures = wg.track_unordered((0..3).map(|i| async move {
+            task::sleep(std::time::Duration::from_millis(10 * (3 - i))).await;
+            i
+        }));
+        assert_eq!(wg.workers(), 3);
+        assert_eq!(futures.next().await, Some(2));
+        assert_eq!(wg.workers(), 2);
+        drop(futures);
+        assert!(wg.is_complete());
+    }
+
+    #[async_std::test]
+    async fn tracked() {
+        let wg = WaitGroup::new();
+        let mut stream = stream::from_iter(0..3).tracked(&wg);
+        let mut items = Vec::new();
+        while let Some(item) = stream.next().await {
+            items.push(item);
+        }
+        assert_eq!(wg.workers(), 3);
+        drop(items);
+        assert!(wg.is_complete());
+    }
+}
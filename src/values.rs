@@ -0,0 +1,103 @@
+use crate::{WaitGroupFuture, Worker};
+use std::any::{Any, TypeId};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A worker that delivers a value of type `T` when it completes.
+///
+/// Created by [`WaitGroup::worker_with_result`](crate::WaitGroup::worker_with_result).
+pub struct ResultWorker<T> {
+    worker: Worker,
+    _value: PhantomData<fn(T)>,
+}
+
+/// Future returned by [`WaitGroup::wait_values`](crate::WaitGroup::wait_values).
+pub struct WaitValues<T> {
+    wait: WaitGroupFuture,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T: Any + Send> ResultWorker<T> {
+    pub(crate) fn new(worker: Worker) -> Self {
+        Self {
+            worker,
+            _value: PhantomData,
+        }
+    }
+
+    /// Delivers `value` and marks the worker as finished.
+    ///
+    /// Returns `true` if this was the last active worker, like [`Worker::done`]. Dropping
+    /// the worker without completing it delivers nothing.
+    pub fn complete(self, value: T) -> bool {
+        let inner = &self.worker.inner;
+        inner
+            .values
+            .lock()
+            .unwrap()
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Vec::<T>::new()))
+            .downcast_mut::<Vec<T>>()
+            .unwrap()
+            .push(value);
+        self.worker.done()
+    }
+
+    /// Gets the underlying worker.
+    pub fn worker(&self) -> &Worker {
+        &self.worker
+    }
+}
+
+impl<T> WaitValues<T> {
+    pub(crate) fn new(wait: WaitGroupFuture) -> Self {
+        Self {
+            wait,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T: Any + Send> Future for WaitValues<T> {
+    type Output = Vec<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if Pin::new(&mut self.wait).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        // the values of other types stay for their own waits
+        let values = self
+            .wait
+            .inner
+            .values
+            .lock()
+            .unwrap()
+            .remove(&TypeId::of::<T>());
+        Poll::Ready(values.map_or_else(Vec::new, |values| *values.downcast::<Vec<T>>().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::WaitGroup;
+    use async_std::task;
+
+    #[async_std::test]
+    async fn wait_values() {
+        let wg = WaitGroup::new();
+        for i in 0..3 {
+            let w = wg.worker_with_result();
+            task::spawn(async move { w.complete(i) });
+        }
+        let w = wg.worker_with_result::<&str>();
+        w.complete("other");
+        drop(wg.worker_with_result::<i32>());
+
+        let mut values = wg.wait_values::<i32>().await;
+        values.sort_unstable();
+        assert_eq!(values, [0, 1, 2]);
+        assert_eq!(wg.wait_values::<&str>().await, ["other"]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::Mutex;
+use std::task::Waker;
+
+/// A set of wakers, each registered together with a condition it waits for.
+///
+/// Each waiting future owns a key into the set, so any number of tasks can wait at once.
+/// The set is behind a `Mutex` by default, [`LocalWaiters`] use a `RefCell` instead.
+pub(crate) struct Waiters<T = (), L = Mutex<Slots<T>>> {
+    slots: L,
+    _cond: PhantomData<fn(T)>,
+}
+
+/// The waiters of a group that never leaves its thread.
+pub(crate) type LocalWaiters = Waiters<(), RefCell<Slots<()>>>;
+
+/// Exclusive access to the slots, shared by the thread-safe and the local groups.
+pub(crate) trait Lock<T> {
+    fn new(value: T) -> Self;
+
+    fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R;
+}
+
+impl<T> Lock<T> for Mutex<T> {
+    fn new(value: T) -> Self {
+        Mutex::new(value)
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.lock().unwrap())
+    }
+}
+
+impl<T> Lock<T> for RefCell<T> {
+    fn new(value: T) -> Self {
+        RefCell::new(value)
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.borrow_mut())
+    }
+}
+
+pub(crate) struct Slots<T> {
+    entries: Vec<Entry<T>>,
+    next_free: usize,
+}
+
+enum Entry<T> {
+    Vacant(usize),
+    Occupied(Option<Waker>, T),
+}
+
+impl<T, L: Lock<Slots<T>>> Waiters<T, L> {
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: L::new(Slots {
+                entries: Vec::new(),
+                next_free: 0,
+            }),
+            _cond: PhantomData,
+        }
+    }
+
+    /// Registers `waker` waiting for `cond` under `key`, allocating a key if there is none yet.
+    pub(crate) fn register(&self, key: &mut Option<usize>, waker: &Waker, cond: T) {
+        self.slots.with(|slots| match *key {
+            Some(k) => match &mut slots.entries[k] {
+                Entry::Occupied(Some(w), c) if w.will_wake(waker) => *c = cond,
+                entry => *entry = Entry::Occupied(Some(waker.clone()), cond),
+            },
+            None => *key = Some(slots.insert(waker.clone(), cond)),
+        })
+    }
+
+    /// Releases `key`, if one was allocated.
+    pub(crate) fn remove(&self, key: &mut Option<usize>) {
+        if let Some(k) = key.take() {
+            self.slots.with(|slots| {
+                slots.entries[k] = Entry::Vacant(slots.next_free);
+                slots.next_free = k;
+            });
+        }
+    }
+
+    /// Gets the number of allocated keys.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.slots.with(|slots| {
+            slots
+                .entries
+                .iter()
+                .filter(|entry| matches!(entry, Entry::Occupied(..)))
+                .count()
+        })
+    }
+
+    /// Wakes every registered waker.
+    pub(crate) fn wake_all(&self) {
+        self.wake(|_| true)
+    }
+
+    /// Wakes the wakers whose condition satisfies `f`.
+    ///
+    /// Keys stay allocated, their owners register again on the next poll.
+    pub(crate) fn wake(&self, mut f: impl FnMut(&T) -> bool) {
+        let mut wakers = Vec::new();
+        self.slots.with(|slots| {
+            for entry in slots.entries.iter_mut() {
+                if let Entry::Occupied(waker, cond) = entry {
+                    if waker.is_some() && f(cond) {
+                        wakers.extend(waker.take());
+                    }
+                }
+            }
+        });
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Slots<T> {
+    fn insert(&mut self, waker: Waker, cond: T) -> usize {
+        let key = self.next_free;
+        if key == self.entries.len() {
+            self.entries.push(Entry::Occupied(Some(waker), cond));
+            self.next_free = key + 1;
+        } else {
+            let entry = mem::replace(&mut self.entries[key], Entry::Occupied(Some(waker), cond));
+            match entry {
+                Entry::Vacant(next) => self.next_free = next,
+                Entry::Occupied(..) => unreachable!("free list points to an occupied entry"),
+            }
+        }
+        key
+    }
+}